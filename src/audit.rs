@@ -0,0 +1,311 @@
+use std::env;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sysinfo::{System, SystemExt};
+
+use crate::file::create_file_reader_for;
+use crate::file::home::get_awsp_file_path;
+
+const AUDIT_FILE_NAME: &str = "audit.log";
+const USER_ENV_VAR_NAMES: &[&str] = &["USER", "USERNAME"];
+const STDIN_LINK: &str = "/proc/self/fd/0";
+
+/// How the switch was made.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SwitchMode {
+    /// profile menu, then region menu
+    #[default]
+    Menu,
+    /// `awsp -r`, region only
+    Region,
+    /// `awsp -`
+    Previous,
+    /// `awsp history`
+    History,
+}
+
+impl fmt::Display for SwitchMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mode = match self {
+            SwitchMode::Menu => "menu",
+            SwitchMode::Region => "region",
+            SwitchMode::Previous => "previous",
+            SwitchMode::History => "history",
+        };
+
+        write!(f, "{}", mode)
+    }
+}
+
+/// One line of `~/.awsp/audit.log`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub user: String,
+    #[serde(default)]
+    pub host: String,
+    #[serde(default)]
+    pub tty: Option<String>,
+    pub profile: String,
+    pub region: String,
+    /// account id derived from the profile without calling AWS
+    #[serde(default)]
+    pub account: Option<String>,
+    pub environment: Option<String>,
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub mode: SwitchMode,
+}
+
+impl AuditEntry {
+    /// Entry for a switch happening now, by the current user on this host and terminal.
+    pub fn new(profile: &str, region: &str, mode: SwitchMode) -> AuditEntry {
+        AuditEntry {
+            timestamp: Utc::now(),
+            user: USER_ENV_VAR_NAMES
+                .iter()
+                .find_map(|name| env::var(name).ok())
+                .unwrap_or_default(),
+            host: System::new().host_name().unwrap_or_default(),
+            tty: fs::read_link(STDIN_LINK)
+                .ok()
+                .map(|path| path.display().to_string())
+                .filter(|path| path.starts_with("/dev/")),
+            profile: profile.to_string(),
+            region: region.to_string(),
+            account: None,
+            environment: None,
+            reason: None,
+            mode,
+        }
+    }
+}
+
+/// When `audit.log` is rotated to `audit.log.1`, `audit.log.2`...
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rotation {
+    /// rotate once the log reaches this many bytes, 0 never rotates
+    pub max_size: u64,
+    /// number of rotated files kept
+    pub keep: usize,
+}
+
+/// Entries `awsp audit` prints, every filter is optional.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditQuery {
+    pub profile: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl AuditQuery {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.profile
+            .as_ref()
+            .is_none_or(|profile| *profile == entry.profile)
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp < until)
+    }
+}
+
+/// Append `entry` to `~/.awsp/audit.log`, rotating it first when it is full.
+pub fn record(entry: &AuditEntry, rotation: Rotation) -> io::Result<()> {
+    append_audit_to(audit_file_path()?.as_path(), entry, rotation)
+}
+
+/// Entries of `~/.awsp/audit.log` and its rotated files, oldest first.
+pub fn load_audit() -> Vec<AuditEntry> {
+    match audit_file_path() {
+        Ok(location) => read_audit_with_rotated_from(location.as_path()),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Append `entry` as one JSON line, entries are never rewritten.
+pub fn append_audit_to(
+    audit_file_path: &Path,
+    entry: &AuditEntry,
+    rotation: Rotation,
+) -> io::Result<()> {
+    if let Some(parent) = audit_file_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    rotate_if_full(audit_file_path, rotation)?;
+
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(audit_file_path)?
+        .write_all(line.as_bytes())
+}
+
+pub fn read_audit_from(audit_file_path: &Path) -> Vec<AuditEntry> {
+    if !audit_file_path.is_file() {
+        return Vec::new();
+    }
+
+    create_file_reader_for(audit_file_path)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect()
+}
+
+pub fn read_audit_with_rotated_from(audit_file_path: &Path) -> Vec<AuditEntry> {
+    let mut rotated_files = (1..)
+        .map(|index| rotated_file_path(audit_file_path, index))
+        .take_while(|path| path.is_file())
+        .collect::<Vec<PathBuf>>();
+    rotated_files.reverse();
+
+    rotated_files
+        .iter()
+        .map(PathBuf::as_path)
+        .chain(std::iter::once(audit_file_path))
+        .flat_map(read_audit_from)
+        .collect()
+}
+
+fn rotate_if_full(audit_file_path: &Path, rotation: Rotation) -> io::Result<()> {
+    let size = fs::metadata(audit_file_path).map_or(0, |metadata| metadata.len());
+    if rotation.max_size == 0 || size < rotation.max_size {
+        return Ok(());
+    }
+
+    if rotation.keep == 0 {
+        return fs::remove_file(audit_file_path);
+    }
+    let oldest = rotated_file_path(audit_file_path, rotation.keep);
+    if oldest.is_file() {
+        fs::remove_file(oldest)?;
+    }
+    for index in (1..rotation.keep).rev() {
+        let rotated = rotated_file_path(audit_file_path, index);
+        if rotated.is_file() {
+            fs::rename(rotated, rotated_file_path(audit_file_path, index + 1))?;
+        }
+    }
+    fs::rename(audit_file_path, rotated_file_path(audit_file_path, 1))
+}
+
+fn rotated_file_path(audit_file_path: &Path, index: usize) -> PathBuf {
+    let mut file_name = audit_file_path.as_os_str().to_owned();
+    file_name.push(format!(".{}", index));
+    PathBuf::from(file_name)
+}
+
+fn audit_file_path() -> io::Result<PathBuf> {
+    get_awsp_file_path(AUDIT_FILE_NAME)
+        .map_err(|e| io::Error::new(io::ErrorKind::NotFound, e.message))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_dir::test_dir;
+
+    const NO_ROTATION: Rotation = Rotation {
+        max_size: 0,
+        keep: 0,
+    };
+
+    fn entry(profile: &str, timestamp: &str) -> AuditEntry {
+        AuditEntry {
+            timestamp: timestamp.parse::<DateTime<Utc>>().unwrap(),
+            user: "alice".to_string(),
+            host: "laptop".to_string(),
+            tty: Some("/dev/pts/1".to_string()),
+            profile: profile.to_string(),
+            region: "eu-west-1".to_string(),
+            account: Some("609629065308".to_string()),
+            environment: Some("prod".to_string()),
+            reason: Some("deploy \"hotfix\"".to_string()),
+            mode: SwitchMode::History,
+        }
+    }
+
+    #[test]
+    fn append_audit_to_should_write_one_json_line_per_entry() {
+        let dir_path = test_dir("audit");
+        let audit_file_path = dir_path.join("audit.log");
+        let entry = entry("prod", "2021-01-01T00:00:00Z");
+
+        append_audit_to(&audit_file_path, &entry, NO_ROTATION).unwrap();
+        append_audit_to(&audit_file_path, &entry, NO_ROTATION).unwrap();
+
+        let content = fs::read_to_string(&audit_file_path).unwrap();
+        let lines = content.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(serde_json::from_str::<AuditEntry>(lines[1]).unwrap(), entry);
+
+        fs::remove_dir_all(&dir_path).unwrap();
+    }
+
+    #[test]
+    fn append_audit_to_should_rotate_full_log_and_keep_given_number_of_files() {
+        let dir_path = test_dir("audit");
+        let audit_file_path = dir_path.join("audit.log");
+        let rotation = Rotation {
+            max_size: 1,
+            keep: 2,
+        };
+
+        for profile in ["a", "b", "c", "d"] {
+            append_audit_to(
+                &audit_file_path,
+                &entry(profile, "2021-01-01T00:00:00Z"),
+                rotation,
+            )
+            .unwrap();
+        }
+
+        let profiles = read_audit_with_rotated_from(&audit_file_path)
+            .into_iter()
+            .map(|entry| entry.profile)
+            .collect::<Vec<String>>();
+        assert_eq!(profiles, vec!["b", "c", "d"]);
+        assert!(!rotated_file_path(&audit_file_path, 3).exists());
+
+        fs::remove_dir_all(&dir_path).unwrap();
+    }
+
+    #[test]
+    fn read_audit_from_should_fill_fields_missing_in_older_entries() {
+        let result = read_audit_from(Path::new("tests/sample-data/awsp_audit.log"));
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].user, "");
+        assert_eq!(result[0].mode, SwitchMode::Menu);
+        assert_eq!(result[1].mode, SwitchMode::Previous);
+        assert_eq!(result[1].account, Some("052310077262".to_string()));
+    }
+
+    #[test]
+    fn audit_query_should_filter_by_profile_and_date() {
+        let entry = entry("prod", "2021-06-15T12:00:00Z");
+        let query = |profile: Option<&str>, since: &str, until: &str| AuditQuery {
+            profile: profile.map(String::from),
+            since: since.parse().ok(),
+            until: until.parse().ok(),
+        };
+
+        assert!(AuditQuery::default().matches(&entry));
+        assert!(
+            query(Some("prod"), "2021-06-15T00:00:00Z", "2021-06-16T00:00:00Z").matches(&entry)
+        );
+        assert!(!query(Some("dev"), "", "").matches(&entry));
+        assert!(!query(None, "2021-06-16T00:00:00Z", "").matches(&entry));
+        assert!(!query(None, "", "2021-06-15T12:00:00Z").matches(&entry));
+    }
+}
@@ -0,0 +1,388 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::file::document::{config_section_title, Document};
+use crate::file::helper::line::{is_valid_value, Sections};
+use crate::profile::is_credentials_key;
+
+/// File of a bundle directory, only one of them may exist.
+const BUNDLE_FILE_NAMES: &[&str] = &["awsp-bundle.toml", "awsp-bundle.yaml", "awsp-bundle.yml"];
+const SSO_SESSION_PREFIX: &str = "sso-session ";
+const MANAGED_NOTICE: &str = "# managed by `awsp bundle apply`, changes here are overwritten";
+
+/// Profiles a team declares once, typically in a git repository its members clone,
+/// for `awsp bundle apply` to write into their config files.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bundle {
+    /// names the block of the config file the bundle owns, so several bundles can share a file
+    pub name: String,
+    /// keys of every profile that does not set them itself
+    #[serde(default)]
+    pub defaults: BTreeMap<String, BundleValue>,
+    /// `[sso-session <name>]` sections
+    #[serde(default)]
+    pub sso_sessions: BTreeMap<String, BTreeMap<String, BundleValue>>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, BTreeMap<String, BundleValue>>,
+}
+
+/// A value of the bundle file, written to the config file as text.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum BundleValue {
+    Text(String),
+    Integer(i64),
+    Float(f64),
+    Flag(bool),
+}
+
+impl fmt::Display for BundleValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BundleValue::Text(text) => write!(f, "{}", text),
+            BundleValue::Integer(integer) => write!(f, "{}", integer),
+            BundleValue::Float(float) => write!(f, "{}", float),
+            BundleValue::Flag(flag) => write!(f, "{}", flag),
+        }
+    }
+}
+
+/// What applying a bundle does to one of its sections.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Add,
+    Update,
+    /// no longer in the bundle
+    Remove,
+    /// declared outside the block by the user, whose section is left as it is
+    Skip,
+}
+
+/// The changes applying a bundle makes to a config file, and the file once they are made.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    /// section names, as the config file names them, with what happens to them
+    pub actions: Vec<(String, Action)>,
+    pub config: Document,
+}
+
+impl Bundle {
+    /// Name and `key = value` pairs of each section the bundle renders, sso-sessions first.
+    /// Keys of a profile are its own and the defaults it does not override, in name order.
+    pub fn sections(&self) -> Vec<(String, Vec<(String, String)>)> {
+        let entries_of = |keys: &BTreeMap<String, BundleValue>| {
+            keys.iter()
+                .map(|(key, value)| (key.clone(), value.to_string()))
+                .collect::<Vec<(String, String)>>()
+        };
+
+        let sessions = self
+            .sso_sessions
+            .iter()
+            .map(|(name, keys)| (format!("{}{}", SSO_SESSION_PREFIX, name), entries_of(keys)));
+        let profiles = self.profiles.iter().map(|(name, keys)| {
+            let mut merged = self.defaults.clone();
+            merged.extend(keys.clone());
+            (name.clone(), entries_of(&merged))
+        });
+
+        sessions.chain(profiles).collect()
+    }
+
+    /// Why the bundle cannot be written to a config file: names that do not fit in a header,
+    /// keys and values that would read back differently, and secrets, which never belong in a
+    /// shared file.
+    pub fn check(&self) -> Result<(), String> {
+        if !is_valid_name(&self.name) {
+            return Err(format!("invalid bundle name '{}'", self.name));
+        }
+        let sections = self
+            .sso_sessions
+            .iter()
+            .chain(&self.profiles)
+            .map(|(name, keys)| (name.as_str(), keys))
+            .chain(std::iter::once(("defaults", &self.defaults)));
+        for (name, keys) in sections {
+            if !is_valid_name(name) {
+                return Err(format!("invalid section name '{}'", name));
+            }
+            for (key, value) in keys {
+                if is_credentials_key(key) {
+                    return Err(format!(
+                        "{} of {} is a secret, keep it out of a shared bundle",
+                        key, name
+                    ));
+                }
+                if !is_valid_key(key) {
+                    return Err(format!("invalid key '{}' in {}", key, name));
+                }
+                if !is_valid_value(&value.to_string()) {
+                    return Err(format!(
+                        "value of {} in {} would be cut at a comment or line break: '{}'",
+                        key, name, value
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// First and last line of the block of the config file the bundle owns.
+    fn markers(&self) -> (String, String) {
+        (
+            format!("# >>> awsp bundle {} >>>", self.name),
+            format!("# <<< awsp bundle {} <<<", self.name),
+        )
+    }
+}
+
+impl Plan {
+    /// Nothing to add, update or remove.
+    pub fn is_empty(&self) -> bool {
+        self.actions
+            .iter()
+            .all(|(_, action)| *action == Action::Skip)
+    }
+}
+
+/// The bundle of the directory `dir`, from its `awsp-bundle.toml`, `.yaml` or `.yml` file.
+pub fn load_bundle(dir: &Path) -> io::Result<Bundle> {
+    let file_path = bundle_file_path_in(dir)?;
+    let content = fs::read_to_string(&file_path)?;
+    let invalid = |message: String| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", file_path.display(), message),
+        )
+    };
+
+    let bundle: Bundle = if file_path.extension().is_some_and(|ext| ext == "toml") {
+        toml::from_str(&content).map_err(|e| invalid(e.to_string()))?
+    } else {
+        serde_yaml::from_str(&content).map_err(|e| invalid(e.to_string()))?
+    };
+    bundle.check().map_err(invalid)?;
+
+    Ok(bundle)
+}
+
+fn bundle_file_path_in(dir: &Path) -> io::Result<PathBuf> {
+    let found = BUNDLE_FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .filter(|path| path.is_file())
+        .collect::<Vec<PathBuf>>();
+
+    match found.as_slice() {
+        [file_path] => Ok(file_path.clone()),
+        [] => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no {} in {}", BUNDLE_FILE_NAMES.join(", "), dir.display()),
+        )),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("more than one bundle file in {}", dir.display()),
+        )),
+    }
+}
+
+/// Reconcile the block `bundle` owns in `config` with the bundle: its sections are rendered in
+/// the block, replacing what the block had, and the block goes once the bundle is empty.
+/// Lines outside the block are never changed, and a section the user declares outside it is
+/// skipped rather than declared twice.
+/// An error when the markers of the block do not pair, e.g. one was deleted by hand.
+pub fn plan(config: &Document, bundle: &Bundle) -> Result<Plan, String> {
+    let (begin, end) = bundle.markers();
+    let current = Document::parse(
+        &config.block(&begin, &end)?.unwrap_or_default().join("\n"),
+        Sections::Config,
+    );
+    let mut outside = config.clone();
+    outside.set_block(&begin, &end, &[])?;
+
+    let mut actions = Vec::new();
+    let mut rendered = Document::parse("", Sections::Config);
+    for (name, entries) in bundle.sections() {
+        if outside.has_section(&name) {
+            actions.push((name, Action::Skip));
+            continue;
+        }
+        rendered.add_section(&config_section_title(&name), &entries);
+        if !current.has_section(&name) {
+            actions.push((name, Action::Add));
+        } else if current.entries(&name) != entries {
+            actions.push((name, Action::Update));
+        }
+    }
+    for name in current.section_names() {
+        if !rendered.has_section(&name) {
+            actions.push((name, Action::Remove));
+        }
+    }
+
+    let mut lines = Vec::new();
+    if !rendered.section_names().is_empty() {
+        lines.push(MANAGED_NOTICE.to_string());
+        lines.extend(rendered.to_string().lines().map(String::from));
+    }
+    let mut after = config.clone();
+    after.set_block(&begin, &end, &lines)?;
+
+    Ok(Plan {
+        actions,
+        config: after,
+    })
+}
+
+/// Fits in a header as one word.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name
+            .chars()
+            .any(|c| c.is_whitespace() || ['[', ']', '"', '\'', '#', ';'].contains(&c))
+}
+
+/// Read back as itself: lowercase, no delimiter or comment.
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUNDLE_DIR: &str = "tests/sample-data/bundle";
+
+    fn bundle() -> Bundle {
+        load_bundle(Path::new(BUNDLE_DIR)).unwrap()
+    }
+
+    #[test]
+    fn load_bundle_should_read_yaml_and_toml_alike() {
+        let yaml = bundle();
+        let toml = load_bundle(&Path::new(BUNDLE_DIR).join("toml")).unwrap();
+
+        assert_eq!(yaml, toml);
+        assert_eq!(
+            yaml.sections()[1],
+            (
+                "platform-dev".to_string(),
+                vec![
+                    ("region".to_string(), "eu-west-1".to_string()),
+                    ("sso_account_id".to_string(), "111111111111".to_string()),
+                    ("sso_role_name".to_string(), "Developer".to_string()),
+                    ("sso_session".to_string(), "platform".to_string()),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn check_should_refuse_secrets_and_names_that_do_not_fit_in_a_header() {
+        let mut bundle = bundle();
+        bundle.profiles.insert(
+            "ci".to_string(),
+            BTreeMap::from([(
+                "aws_secret_access_key".to_string(),
+                BundleValue::Text("je7MtGbClwBF".to_string()),
+            )]),
+        );
+        assert!(bundle.check().unwrap_err().contains("secret"));
+
+        let mut bundle = self::bundle();
+        bundle
+            .profiles
+            .insert("team dev".to_string(), BTreeMap::new());
+        assert!(bundle.check().is_err());
+
+        let mut bundle = self::bundle();
+        bundle.defaults.insert(
+            "Region".to_string(),
+            BundleValue::Text("eu-west-1".to_string()),
+        );
+        assert!(bundle.check().is_err());
+
+        for value in [
+            "arn:aws:iam::111111111111:role/Admin #prod",
+            "a ;b",
+            "#a",
+            "a\nb",
+        ] {
+            let mut bundle = self::bundle();
+            bundle
+                .defaults
+                .insert("role_arn".to_string(), BundleValue::Text(value.to_string()));
+            assert!(bundle.check().is_err(), "{}", value);
+        }
+        let mut bundle = self::bundle();
+        bundle.defaults.insert(
+            "role_arn".to_string(),
+            BundleValue::Text("arn:aws:iam::111111111111:role/Admin#prod".to_string()),
+        );
+        assert!(bundle.check().is_ok());
+    }
+
+    #[test]
+    fn plan_should_add_update_and_remove_only_inside_the_block() {
+        let config =
+            Document::load(&Path::new(BUNDLE_DIR).join("config"), Sections::Config).unwrap();
+        let mut bundle = bundle();
+
+        let first = plan(&config, &bundle).unwrap();
+        assert_eq!(
+            first.actions,
+            vec![
+                ("sso-session platform".to_string(), Action::Add),
+                ("platform-dev".to_string(), Action::Add),
+                ("platform-prod".to_string(), Action::Add),
+                ("team".to_string(), Action::Skip),
+            ]
+        );
+        assert_eq!(first.config.entries("team"), config.entries("team"));
+        assert!(plan(&first.config, &bundle).unwrap().is_empty());
+
+        bundle.profiles.remove("platform-dev");
+        bundle.defaults.insert(
+            "region".to_string(),
+            BundleValue::Text("eu-central-1".to_string()),
+        );
+        let second = plan(&first.config, &bundle).unwrap();
+        assert_eq!(
+            second.actions,
+            vec![
+                ("platform-prod".to_string(), Action::Update),
+                ("team".to_string(), Action::Skip),
+                ("platform-dev".to_string(), Action::Remove),
+            ]
+        );
+        assert!(!second.config.has_section("platform-dev"));
+
+        bundle.profiles.clear();
+        bundle.sso_sessions.clear();
+        let emptied = plan(&second.config, &bundle).unwrap();
+        assert_eq!(emptied.config, config);
+    }
+
+    #[test]
+    fn plan_should_refuse_a_block_whose_end_marker_is_gone() {
+        let config =
+            Document::load(&Path::new(BUNDLE_DIR).join("config"), Sections::Config).unwrap();
+        let bundle = bundle();
+        let applied = plan(&config, &bundle).unwrap().config.to_string();
+        let (begin, end) = bundle.markers();
+
+        let dangling = Document::parse(&applied.replace(&end, ""), Sections::Config);
+
+        assert!(plan(&dangling, &bundle).unwrap_err().contains(&begin));
+    }
+}
@@ -4,8 +4,12 @@ use structopt::StructOpt;
 #[derive(Debug, StructOpt)]
 #[structopt(name = "aws ops", about = "AWS Configure Profile")]
 pub struct Opt {
-    #[structopt(short = "r", long = "region", help = "Region Selector")]
-    pub region: bool,
+    #[structopt(
+        short = "r",
+        long = "region",
+        help = "Region Selector, or set the given region without a menu"
+    )]
+    pub region: Option<Option<String>>,
     // TODO add explicit profile
     // #[structopt(short = "p", long = "profile")]
     // pub profile: String,
@@ -19,6 +23,236 @@ pub struct Opt {
         help = "Override an aws configuration file (default = ~/.aws/config)"
     )]
     pub config: Option<PathBuf>,
+
+    #[structopt(
+        short = "a",
+        long = "account",
+        help = "Only list profiles of the given account ID"
+    )]
+    pub account: Option<String>,
+
+    #[structopt(
+        long = "any-partition",
+        help = "List regions of every partition, not only the selected profile's"
+    )]
+    pub any_partition: bool,
+
+    #[structopt(
+        long = "keep-region",
+        help = "Skip the region menu when the selected profile configures a region"
+    )]
+    pub keep_region: bool,
+
+    #[structopt(
+        long = "verify",
+        help = "Check the selected profile with STS GetCallerIdentity after switching"
+    )]
+    pub verify: bool,
+
+    #[structopt(
+        long = "strict",
+        global = true,
+        help = "Refuse to run when the config or credentials file has invalid values or misspelled keys"
+    )]
+    pub strict: bool,
+
+    #[structopt(
+        long = "lenient-sections",
+        global = true,
+        help = "Read [profile x] and [x] as profile x in both files, as awsp did before following the AWS CLI"
+    )]
+    pub lenient_sections: bool,
+
+    #[structopt(
+        long = "sts-endpoint",
+        help = "Override the STS endpoint (default = $AWSP_STS_ENDPOINT or the regional endpoint)"
+    )]
+    pub sts_endpoint: Option<String>,
+
+    #[structopt(
+        name = "-",
+        possible_values = &["-"],
+        help = "Switch back to the previous profile and region"
+    )]
+    pub previous: Option<String>,
+
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum Command {
+    #[structopt(about = "Show the account, ARN and user ID of the current profile")]
+    Whoami {
+        #[structopt(
+            short = "p",
+            long = "profile",
+            help = "Profile to check (default = $AWS_PROFILE)"
+        )]
+        profile: Option<String>,
+    },
+
+    #[structopt(about = "List recent switches and switch to one of them")]
+    History {
+        #[structopt(short = "l", long = "list", help = "Only print the switch history")]
+        list: bool,
+    },
+
+    #[structopt(
+        about = "Pin a profile to the top of the profile menu, list pins without <profile>"
+    )]
+    Pin { profile: Option<String> },
+
+    #[structopt(about = "Unpin a profile")]
+    Unpin { profile: String },
+
+    #[structopt(about = "List profiles of the config and credentials files")]
+    List {
+        #[structopt(
+            short = "o",
+            long = "output",
+            default_value = "table",
+            possible_values = &["table", "json", "csv"],
+            help = "Output format"
+        )]
+        output: String,
+
+        #[structopt(long = "filter", help = "Only profiles whose name matches this regex")]
+        filter: Option<String>,
+
+        #[structopt(
+            long = "columns",
+            use_delimiter = true,
+            possible_values = &["name", "type", "region", "account", "source", "file", "line"],
+            help = "Comma separated columns to print (default = all)"
+        )]
+        columns: Vec<String>,
+    },
+
+    #[structopt(about = "Add a profile with an interactive wizard")]
+    Add,
+
+    #[structopt(about = "Change keys of a profile, interactively without --set or --unset")]
+    Edit {
+        profile: String,
+
+        #[structopt(long = "set", number_of_values = 1, help = "key=value to set")]
+        set: Vec<String>,
+
+        #[structopt(long = "unset", number_of_values = 1, help = "Key to remove")]
+        unset: Vec<String>,
+    },
+
+    #[structopt(about = "Rename a profile and every source_profile pointing at it")]
+    Rename { profile: String, new_name: String },
+
+    #[structopt(about = "Copy a profile to a new name")]
+    Copy { profile: String, new_name: String },
+
+    #[structopt(about = "Remove a profile from the config and credentials files")]
+    Rm { profile: String },
+
+    #[structopt(about = "List or restore the backups awsp takes before writing AWS config files")]
+    Backup(BackupCommand),
+
+    #[structopt(
+        about = "Write the profiles of a team bundle to a managed block of the config file"
+    )]
+    Bundle(BundleCommand),
+
+    #[structopt(
+        about = "Check the config and credentials files for permission and hygiene problems"
+    )]
+    Doctor {
+        #[structopt(
+            long = "fix",
+            help = "Repair the problems that can be, showing a diff first"
+        )]
+        fix: bool,
+    },
+
+    #[structopt(about = "Show every key of a profile with the file:line it comes from")]
+    Show {
+        profile: String,
+
+        #[structopt(long = "reveal", help = "Print secrets instead of masking them")]
+        reveal: bool,
+    },
+
+    #[structopt(about = "Query the audit log of profile switches")]
+    Audit {
+        #[structopt(short = "p", long = "profile", help = "Only switches to this profile")]
+        profile: Option<String>,
+
+        #[structopt(
+            long = "since",
+            help = "Only switches from this day on, YYYY-MM-DD (local time) or RFC 3339"
+        )]
+        since: Option<String>,
+
+        #[structopt(
+            long = "until",
+            help = "Only switches up to this day or time included, YYYY-MM-DD (local time) or RFC 3339"
+        )]
+        until: Option<String>,
+
+        #[structopt(long = "json", help = "Print matching entries as JSON lines")]
+        json: bool,
+    },
+
+    #[structopt(about = "Show the profile, region and credential expiry of this shell")]
+    Current {
+        #[structopt(long = "json", help = "Print as JSON")]
+        json: bool,
+    },
+
+    #[structopt(about = "Print a one-line status for shell prompts")]
+    Prompt {
+        #[structopt(
+            short = "f",
+            long = "format",
+            default_value = "{profile}@{region}{expiry}",
+            help = "Template of {profile}, {region}, {account} and {expiry}"
+        )]
+        format: String,
+
+        #[structopt(
+            long = "preset",
+            possible_values = &["starship", "p10k", "ps1"],
+            help = "Print the prompt configuration snippet for a shell theme instead"
+        )]
+        preset: Option<String>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum BackupCommand {
+    #[structopt(about = "List backups, oldest first")]
+    List,
+
+    #[structopt(about = "Put the files of a backup back in place")]
+    Restore { id: String },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum BundleCommand {
+    #[structopt(about = "Show what apply would change, without writing")]
+    Plan {
+        #[structopt(
+            parse(from_os_str),
+            help = "Directory of the awsp-bundle.toml or .yaml file"
+        )]
+        path: PathBuf,
+    },
+
+    #[structopt(about = "Reconcile the managed block with the bundle, showing a diff first")]
+    Apply {
+        #[structopt(
+            parse(from_os_str),
+            help = "Directory of the awsp-bundle.toml or .yaml file"
+        )]
+        path: PathBuf,
+    },
 }
 
 impl Opt {
@@ -0,0 +1,65 @@
+pub mod add;
+pub mod audit;
+pub mod backup;
+pub mod bundle;
+pub mod current;
+pub mod doctor;
+pub mod edit;
+pub mod history;
+pub mod list;
+pub mod pin;
+pub mod preview;
+pub mod prompt;
+pub mod show;
+pub mod whoami;
+
+use crate::cmdline::{BackupCommand, BundleCommand, Command, Opt};
+
+pub fn run(command: &Command, opt: &Opt) {
+    match command {
+        Command::Whoami { profile } => whoami::run(
+            profile.as_deref(),
+            opt.sts_endpoint.as_deref(),
+            opt.lenient_sections,
+        ),
+        Command::History { list } => history::run(opt, *list),
+        Command::Pin { profile } => pin::run_pin(profile.as_deref(), opt.lenient_sections),
+        Command::Unpin { profile } => pin::run_unpin(profile),
+        Command::List {
+            output,
+            filter,
+            columns,
+        } => list::run(output, filter.as_deref(), columns, opt.lenient_sections),
+        Command::Add => add::run(opt.lenient_sections),
+        Command::Edit {
+            profile,
+            set,
+            unset,
+        } => edit::run_edit(profile, set, unset, opt.lenient_sections),
+        Command::Rename { profile, new_name } => {
+            edit::run_rename(profile, new_name, opt.lenient_sections)
+        }
+        Command::Copy { profile, new_name } => {
+            edit::run_copy(profile, new_name, opt.lenient_sections)
+        }
+        Command::Rm { profile } => edit::run_rm(profile, opt.lenient_sections),
+        Command::Backup(BackupCommand::List) => backup::run_list(),
+        Command::Backup(BackupCommand::Restore { id }) => {
+            backup::run_restore(id, opt.lenient_sections)
+        }
+        Command::Bundle(BundleCommand::Plan { path }) => bundle::run_plan(path),
+        Command::Bundle(BundleCommand::Apply { path }) => {
+            bundle::run_apply(path, opt.lenient_sections)
+        }
+        Command::Doctor { fix } => doctor::run(*fix, opt.lenient_sections),
+        Command::Show { profile, reveal } => show::run(profile, *reveal, opt.lenient_sections),
+        Command::Audit {
+            profile,
+            since,
+            until,
+            json,
+        } => audit::run(profile.clone(), since.as_deref(), until.as_deref(), *json),
+        Command::Current { json } => current::run(*json),
+        Command::Prompt { format, preset } => prompt::run(format, preset.as_deref()),
+    }
+}
@@ -0,0 +1,110 @@
+use std::process;
+
+use awsp::audit::{load_audit, AuditEntry, AuditQuery};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+pub fn run(profile: Option<String>, since: Option<&str>, until: Option<&str>, json: bool) {
+    let query = AuditQuery {
+        profile,
+        since: since.map(|since| parse_bound(since, false)),
+        until: until.map(|until| parse_bound(until, true)),
+    };
+
+    let entries = load_audit()
+        .into_iter()
+        .filter(|entry| query.matches(entry))
+        .collect::<Vec<AuditEntry>>();
+    if entries.is_empty() && !json {
+        yellow_ln!("\nNo switch in audit log\n");
+        return;
+    }
+
+    for entry in &entries {
+        if json {
+            println!("{}", serde_json::to_string(entry).unwrap_or_default());
+        } else {
+            println!("{}", audit_label(entry));
+        }
+    }
+}
+
+/// `time  user@host  profile  region  account  mode  reason`
+fn audit_label(entry: &AuditEntry) -> String {
+    let mut label = format!(
+        "{}  {}@{}  {}  {}  {}  {}",
+        entry
+            .timestamp
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M"),
+        entry.user,
+        entry.host,
+        entry.profile,
+        entry.region,
+        entry.account.as_deref().unwrap_or("-"),
+        entry.mode
+    );
+    if let Some(reason) = &entry.reason {
+        label.push_str("  ");
+        label.push_str(reason);
+    }
+
+    label
+}
+
+/// `YYYY-MM-DD` is a local day, `until` of a day ends when the next day starts. `until` of a
+/// timestamp ends right after it, so that both are included.
+/// Exit when `value` is not a date.
+fn parse_bound(value: &str, is_until: bool) -> DateTime<Utc> {
+    if let Ok(timestamp) = value.parse::<DateTime<Utc>>() {
+        return if is_until {
+            timestamp + Duration::nanoseconds(1)
+        } else {
+            timestamp
+        };
+    }
+
+    let day = match NaiveDate::parse_from_str(value, DATE_FORMAT) {
+        Ok(day) if is_until => day + Duration::days(1),
+        Ok(day) => day,
+        Err(_) => {
+            red_ln!("\nInvalid date {}, expected YYYY-MM-DD\n", value);
+            process::exit(1);
+        }
+    };
+
+    let start = day.and_hms_opt(0, 0, 0).unwrap();
+    Local
+        .from_local_datetime(&start)
+        .earliest()
+        .map(|start| start.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&start))
+}
+
+#[cfg(test)]
+mod tests {
+    use awsp::audit::SwitchMode;
+
+    use super::*;
+
+    fn switch_at(timestamp: &str) -> AuditEntry {
+        AuditEntry {
+            timestamp: timestamp.parse().unwrap(),
+            ..AuditEntry::new("prod", "eu-west-1", SwitchMode::Menu)
+        }
+    }
+
+    #[test]
+    fn parse_bound_should_include_the_until_timestamp() {
+        let query = AuditQuery {
+            since: Some(parse_bound("2021-06-15T12:00:00Z", false)),
+            until: Some(parse_bound("2021-06-15T12:00:00Z", true)),
+            ..AuditQuery::default()
+        };
+
+        assert!(query.matches(&switch_at("2021-06-15T12:00:00Z")));
+        assert!(!query.matches(&switch_at("2021-06-15T12:00:00.001Z")));
+        assert!(!query.matches(&switch_at("2021-06-15T11:59:59.999Z")));
+    }
+}
@@ -0,0 +1,88 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use awsp::file::config::get_aws_config_file_path;
+use awsp::file::credential::get_aws_credentials_file_path;
+use awsp::file::document::Document;
+use awsp::file::helper::line::Sections;
+use awsp::file::safe_write::{list_backups, resolve_file_path, Backup};
+
+use crate::command::preview::{confirm_and_save, PendingWrite};
+
+pub fn run_list() {
+    let backups = backups();
+    if backups.is_empty() {
+        yellow_ln!("\nNo backup yet\n");
+        return;
+    }
+
+    let id_width = backups
+        .iter()
+        .map(|backup| backup.id.len())
+        .max()
+        .unwrap_or(0);
+    for backup in backups {
+        println!(
+            "{:id_width$}  {}",
+            backup.id,
+            backup.file_path.display(),
+            id_width = id_width
+        );
+    }
+}
+
+/// Restore every file backed up under `id`, showing the diff first. The current files are backed up too.
+pub fn run_restore(id: &str, lenient: bool) {
+    let backups = backups()
+        .into_iter()
+        .filter(|backup| backup.id == id)
+        .collect::<Vec<Backup>>();
+    if backups.is_empty() {
+        red_ln!("\nNo backup with id {}, see awsp backup list\n", id);
+        process::exit(1);
+    }
+
+    let writes = backups
+        .iter()
+        .map(|backup| {
+            match (
+                Document::load(&backup.file_path, Sections::Titles),
+                fs::read_to_string(&backup.backup_path),
+            ) {
+                (Ok(before), Ok(content)) => PendingWrite {
+                    path: backup.file_path.clone(),
+                    before,
+                    after: Document::parse(&content, Sections::Titles),
+                },
+                (Err(e), _) | (_, Err(e)) => {
+                    red_ln!("\nFailed to read {}: {}\n", backup.backup_path.display(), e);
+                    process::exit(1);
+                }
+            }
+        })
+        .collect::<Vec<PendingWrite>>();
+
+    if confirm_and_save(&writes, Sections::of_config_file(lenient)) {
+        green_ln!("\nRestored backup {}\n", id);
+    }
+}
+
+/// Backups of the directories of the config and credentials files, and of the files they
+/// point to when they are symlinks.
+fn backups() -> Vec<Backup> {
+    let dir_paths = [get_aws_config_file_path(), get_aws_credentials_file_path()]
+        .iter()
+        .flatten()
+        .flat_map(|file_path| [Ok(file_path.clone()), resolve_file_path(file_path)])
+        .flatten()
+        .filter_map(|file_path| file_path.parent().map(Path::to_path_buf))
+        .collect::<Vec<PathBuf>>();
+
+    list_backups(
+        &dir_paths
+            .iter()
+            .map(PathBuf::as_path)
+            .collect::<Vec<&Path>>(),
+    )
+}
@@ -0,0 +1,89 @@
+use std::path::Path;
+use std::process;
+
+use awsp::bundle::{load_bundle, plan, Action, Plan};
+use awsp::file::config::get_aws_config_file_path;
+use awsp::file::document::Document;
+use awsp::file::helper::line::Sections;
+
+use crate::command::preview::{confirm_and_save, print_diff, PendingWrite};
+
+pub fn run_plan(path: &Path) {
+    let (write, plan) = plan_for(path);
+    print_plan(&plan);
+    if plan.is_empty() {
+        return;
+    }
+
+    print_diff(&write);
+    println!("\nWrite it with: awsp bundle apply {}\n", path.display());
+}
+
+pub fn run_apply(path: &Path, lenient: bool) {
+    let (write, plan) = plan_for(path);
+    print_plan(&plan);
+    if plan.is_empty() {
+        return;
+    }
+
+    if confirm_and_save(&[write], Sections::of_config_file(lenient)) {
+        green_ln!("\nApplied bundle {}\n", path.display());
+    }
+}
+
+/// The bundle of `path` reconciled with the user's config file, exits when either cannot be read.
+fn plan_for(path: &Path) -> (PendingWrite, Plan) {
+    let bundle = load_bundle(path).unwrap_or_else(|e| {
+        red_ln!("\nFailed to read the bundle: {}\n", e);
+        process::exit(1);
+    });
+    let config_file_path = get_aws_config_file_path().unwrap_or_else(|e| {
+        red_ln!("\n{}\n", e.message);
+        process::exit(1);
+    });
+    let before = Document::load(&config_file_path, Sections::Config).unwrap_or_else(|e| {
+        red_ln!("\nFailed to read {}: {}\n", config_file_path.display(), e);
+        process::exit(1);
+    });
+
+    let plan = plan(&before, &bundle).unwrap_or_else(|e| {
+        red_ln!(
+            "\nThe bundle block of {} is damaged, fix it by hand: {}\n",
+            config_file_path.display(),
+            e
+        );
+        process::exit(1);
+    });
+    let write = PendingWrite {
+        path: config_file_path,
+        before,
+        after: plan.config.clone(),
+    };
+    (write, plan)
+}
+
+fn print_plan(plan: &Plan) {
+    println!();
+    for (section, action) in &plan.actions {
+        match action {
+            Action::Add => {
+                green_ln!("+ {}", section);
+            }
+            Action::Update => {
+                yellow_ln!("~ {}", section);
+            }
+            Action::Remove => {
+                red_ln!("- {}", section);
+            }
+            Action::Skip => {
+                e_yellow_ln!(
+                    "! {} is declared outside the bundle block, yours is left as it is",
+                    section
+                );
+            }
+        }
+    }
+    if plan.is_empty() {
+        yellow_ln!("The config file is up to date with the bundle\n");
+    }
+}
@@ -0,0 +1,49 @@
+use std::process;
+
+use awsp::session::{countdown_label, Session};
+use chrono::{Local, Utc};
+use serde_json::json;
+
+pub fn run(json: bool) {
+    let session = Session::current();
+    let now = Utc::now();
+
+    if json {
+        let value = match &session {
+            Some(session) => json!({
+                "profile": session.profile,
+                "region": session.region,
+                "account": session.account,
+                "expiry": session.expiry.map(|expiry| expiry.to_rfc3339()),
+                "expires_in_seconds": session.expires_in(now).map(|left| left.num_seconds()),
+            }),
+            None => json!({
+                "profile": null,
+                "region": null,
+                "account": null,
+                "expiry": null,
+                "expires_in_seconds": null,
+            }),
+        };
+        println!("{}", value);
+        return;
+    }
+
+    let session = match session {
+        Some(session) => session,
+        None => {
+            yellow_ln!("No profile active, AWS_PROFILE is not set");
+            process::exit(1);
+        }
+    };
+    println!("profile  {}", session.profile);
+    println!("region   {}", session.region.as_deref().unwrap_or("-"));
+    println!("account  {}", session.account.as_deref().unwrap_or("-"));
+    if let (Some(expiry), Some(left)) = (session.expiry, session.expires_in(now)) {
+        println!(
+            "expires  {} ({})",
+            expiry.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            countdown_label(left)
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::path::Path;
+use std::process;
+
+use awsp::doctor::{check_file, fix_content, fix_mode, FileKind, Finding, Problem, PRIVATE_MODE};
+use awsp::file::config::get_aws_config_file_path;
+use awsp::file::credential::get_aws_credentials_file_path;
+use awsp::file::document::Document;
+use awsp::file::helper::line::Sections;
+
+use crate::command::preview::{confirm_and_save, PendingWrite};
+
+pub fn run(fix: bool, lenient: bool) {
+    let (config_file_path, credentials_file_path) =
+        match (get_aws_config_file_path(), get_aws_credentials_file_path()) {
+            (Ok(config), Ok(credentials)) => (config, credentials),
+            (Err(e), _) | (_, Err(e)) => {
+                red_ln!("\n{}\n", e.message);
+                process::exit(1);
+            }
+        };
+
+    let mut findings = Vec::new();
+    for (file_path, kind) in [
+        (&config_file_path, FileKind::Config),
+        (&credentials_file_path, FileKind::Credentials),
+    ] {
+        match check_file(file_path, kind) {
+            Ok(file_findings) => findings.extend(file_findings),
+            Err(e) => {
+                red_ln!("\nFailed to read {}: {}\n", file_path.display(), e);
+                process::exit(1);
+            }
+        }
+    }
+
+    if findings.is_empty() {
+        green_ln!("No problem found");
+        return;
+    }
+    for finding in &findings {
+        print_finding(finding);
+    }
+    if !fix {
+        if findings.iter().any(|finding| finding.problem.is_fixable()) {
+            println!("\nRun awsp doctor --fix to repair the problems marked (fixable)");
+        }
+        process::exit(1);
+    }
+
+    for finding in &findings {
+        if let Problem::LooseMode(_) = finding.problem {
+            match fix_mode(&finding.file) {
+                Ok(()) => {
+                    green_ln!("chmod {:o} {}", PRIVATE_MODE, finding.file.display());
+                }
+                Err(e) => {
+                    e_red_ln!("Failed to chmod {}: {}", finding.file.display(), e);
+                }
+            }
+        }
+    }
+    fix_files(
+        &findings,
+        &config_file_path,
+        &credentials_file_path,
+        lenient,
+    );
+}
+
+/// Exit listing the schema violations of the config and credentials files, if any refuses to
+/// run, see `Problem::is_strict_violation`. Unknown keys without a suggestion are warned about.
+pub fn enforce_schema() {
+    let (config_file_path, credentials_file_path) =
+        match (get_aws_config_file_path(), get_aws_credentials_file_path()) {
+            (Ok(config), Ok(credentials)) => (config, credentials),
+            (Err(e), _) | (_, Err(e)) => {
+                red_ln!("\n{}\n", e.message);
+                process::exit(1);
+            }
+        };
+
+    let violations = [
+        (&config_file_path, FileKind::Config),
+        (&credentials_file_path, FileKind::Credentials),
+    ]
+    .iter()
+    .filter_map(|(file_path, kind)| check_file(file_path, *kind).ok())
+    .flatten()
+    .filter(|finding| finding.problem.is_schema_violation())
+    .collect::<Vec<Finding>>();
+    if violations.is_empty() {
+        return;
+    }
+
+    if !violations
+        .iter()
+        .any(|finding| finding.problem.is_strict_violation())
+    {
+        // on stderr, the output of the command that runs next may be read by a script
+        for finding in &violations {
+            e_yellow_ln!("{}  {}", location_of(finding), finding.problem);
+        }
+        e_yellow_ln!("--strict: awsp does not know the keys above, check them if the AWS CLI does not either");
+        return;
+    }
+
+    for finding in &violations {
+        print_finding(finding);
+    }
+    red_ln!("\n--strict: fix the keys above first\n");
+    process::exit(1);
+}
+
+fn print_finding(finding: &Finding) {
+    let fixable = if finding.problem.is_fixable() {
+        " (fixable)"
+    } else {
+        ""
+    };
+
+    if finding.problem.is_error() {
+        red!("{}", location_of(finding));
+        println!("  error: {}{}", finding.problem, fixable);
+    } else {
+        yellow!("{}", location_of(finding));
+        println!("  {}{}", finding.problem, fixable);
+    }
+}
+
+fn location_of(finding: &Finding) -> String {
+    match finding.line {
+        Some(line) => format!("{}:{}", finding.file.display(), line),
+        None => finding.file.display().to_string(),
+    }
+}
+
+fn fix_files(
+    findings: &[Finding],
+    config_file_path: &Path,
+    credentials_file_path: &Path,
+    lenient: bool,
+) {
+    let (config, credentials) = match (
+        Document::load(config_file_path, FileKind::Config.sections()),
+        Document::load(credentials_file_path, FileKind::Credentials.sections()),
+    ) {
+        (Ok(config), Ok(credentials)) => (config, credentials),
+        (Err(e), _) | (_, Err(e)) => {
+            red_ln!("\nFailed to read AWS config files: {}\n", e);
+            process::exit(1);
+        }
+    };
+    let mut fixed_config = config.clone();
+    let mut fixed_credentials = credentials.clone();
+    let conflicts = fix_content(findings, &mut fixed_config, &mut fixed_credentials);
+
+    let writes = [
+        PendingWrite {
+            path: config_file_path.to_path_buf(),
+            before: config,
+            after: fixed_config,
+        },
+        PendingWrite {
+            path: credentials_file_path.to_path_buf(),
+            before: credentials,
+            after: fixed_credentials,
+        },
+    ];
+    if writes.iter().any(|write| write.before != write.after)
+        && confirm_and_save(&writes, Sections::of_config_file(lenient))
+    {
+        green_ln!("\nFixed\n");
+    }
+
+    for finding in &conflicts {
+        yellow!("{}", location_of(finding));
+        println!("  {}, {}", finding.problem, finding.problem.conflict());
+    }
+    if findings.iter().any(|finding| finding.problem.is_error()) {
+        red_ln!("\nThe AWS CLI cannot load these files until the errors above are fixed by hand\n");
+        process::exit(1);
+    }
+    if !conflicts.is_empty() {
+        process::exit(1);
+    }
+}
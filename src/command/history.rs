@@ -0,0 +1,67 @@
+use std::process;
+
+use awsp::audit::SwitchMode;
+use awsp::history::{load_history, HistoryEntry};
+use chrono::Local;
+
+use crate::cmdline::Opt;
+use crate::finder;
+use crate::selector::{commit_switch, select_profile, select_region};
+
+pub fn run(opt: &Opt, list: bool) {
+    let history = load_history();
+    if history.is_empty() {
+        yellow_ln!("\nSwitch history is empty\n");
+        return;
+    }
+
+    if list {
+        for entry in history.iter().rev() {
+            println!("{}", history_label(entry, &history));
+        }
+        return;
+    }
+
+    // most recent switch of each profile/region pair
+    let mut entries: Vec<&HistoryEntry> = Vec::new();
+    for entry in history.iter().rev() {
+        if !entries
+            .iter()
+            .any(|e| e.profile == entry.profile && e.region == entry.region)
+        {
+            entries.push(entry);
+        }
+    }
+
+    let display_list = entries
+        .iter()
+        .map(|entry| history_label(entry, &history))
+        .collect::<Vec<String>>();
+    let selection = match finder::pick("history", &display_list, 0) {
+        Ok(Some(selection)) => selection,
+        _ => process::exit(1),
+    };
+
+    select_profile(&entries[selection].profile);
+    select_region(&entries[selection].region);
+    commit_switch(opt, SwitchMode::History);
+}
+
+/// `time  profile  region  cwd`, columns aligned over the whole history.
+fn history_label(entry: &HistoryEntry, history: &[HistoryEntry]) -> String {
+    let profile_width = history.iter().map(|e| e.profile.len()).max().unwrap_or(0);
+    let region_width = history.iter().map(|e| e.region.len()).max().unwrap_or(0);
+
+    format!(
+        "{}  {:profile_width$}  {:region_width$}  {}",
+        entry
+            .timestamp
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M"),
+        entry.profile,
+        entry.region,
+        entry.cwd,
+        profile_width = profile_width,
+        region_width = region_width
+    )
+}
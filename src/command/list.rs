@@ -0,0 +1,206 @@
+use std::process;
+
+use awsp::file::credential::get_aws_credentials_file_path;
+use awsp::file::helper::line::Sections;
+use awsp::file::layers::LayeredConfig;
+use awsp::profile::{summarize_profiles, ProfileSummary};
+use regex::Regex;
+use serde_json::{Map, Value};
+
+const ALL_COLUMNS: &[&str] = &[
+    "name", "type", "region", "account", "source", "file", "line",
+];
+
+pub fn run(output: &str, filter: Option<&str>, columns: &[String], lenient: bool) {
+    let filter = match filter.map(Regex::new).transpose() {
+        Ok(filter) => filter,
+        Err(e) => {
+            red_ln!("\nInvalid filter: {}\n", e);
+            process::exit(1);
+        }
+    };
+    let columns = if columns.is_empty() {
+        ALL_COLUMNS
+            .iter()
+            .map(|column| column.to_string())
+            .collect()
+    } else {
+        columns.to_vec()
+    };
+
+    let (config, credentials_file_path) = match (
+        LayeredConfig::load_default(Sections::of_config_file(lenient)),
+        get_aws_credentials_file_path(),
+    ) {
+        (Ok(config), Ok(credentials)) => (config, credentials),
+        (Err(e), _) | (_, Err(e)) => {
+            red_ln!("\n{}\n", e.message);
+            process::exit(1);
+        }
+    };
+    let profiles = summarize_profiles(&config, &credentials_file_path, lenient)
+        .into_iter()
+        .filter(|summary| filter.as_ref().is_none_or(|f| f.is_match(&summary.name)))
+        .collect::<Vec<ProfileSummary>>();
+
+    match output {
+        "json" => println!("{}", to_json(&profiles, &columns)),
+        "csv" => print!("{}", to_csv(&profiles, &columns)),
+        _ => print!("{}", to_table(&profiles, &columns)),
+    }
+}
+
+/// Array of objects keyed by column, `null` for unknown values and `line` as a number.
+fn to_json(profiles: &[ProfileSummary], columns: &[String]) -> String {
+    let rows = profiles
+        .iter()
+        .map(|summary| {
+            let row = columns
+                .iter()
+                .map(|column| {
+                    let value = match column.as_str() {
+                        "line" => Value::from(summary.line),
+                        _ => cell(summary, column).map_or(Value::Null, Value::from),
+                    };
+                    (column.clone(), value)
+                })
+                .collect::<Map<String, Value>>();
+            Value::Object(row)
+        })
+        .collect::<Vec<Value>>();
+
+    serde_json::to_string_pretty(&rows).unwrap_or_default()
+}
+
+fn to_csv(profiles: &[ProfileSummary], columns: &[String]) -> String {
+    let escape = |value: &str| {
+        if value.contains([',', '"', '\n']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    };
+
+    let mut csv = columns.join(",") + "\n";
+    for summary in profiles {
+        let row = columns
+            .iter()
+            .map(|column| escape(&cell(summary, column).unwrap_or_default()))
+            .collect::<Vec<String>>();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+/// Header and rows with aligned columns, `-` for unknown values.
+fn to_table(profiles: &[ProfileSummary], columns: &[String]) -> String {
+    let mut rows = vec![columns.iter().map(|c| c.to_uppercase()).collect::<Vec<_>>()];
+    for summary in profiles {
+        rows.push(
+            columns
+                .iter()
+                .map(|column| cell(summary, column).unwrap_or_else(|| "-".to_string()))
+                .collect(),
+        );
+    }
+    let widths = (0..columns.len())
+        .map(|index| rows.iter().map(|row| row[index].len()).max().unwrap_or(0))
+        .collect::<Vec<usize>>();
+
+    rows.iter()
+        .map(|row| {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(value, width)| format!("{:width$}", value, width = width))
+                .collect::<Vec<String>>()
+                .join("  ");
+            line.trim_end().to_string() + "\n"
+        })
+        .collect()
+}
+
+fn cell(summary: &ProfileSummary, column: &str) -> Option<String> {
+    match column {
+        "name" => Some(summary.name.clone()),
+        "type" => Some(summary.kind.to_string()),
+        "region" => summary.region.clone(),
+        "account" => summary.account.clone(),
+        "source" => Some(summary.origin.to_string()),
+        "file" => Some(summary.file.display().to_string()),
+        "line" => Some(summary.line.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use awsp::file::layers::Origin;
+    use awsp::profile::ProfileKind;
+
+    use super::*;
+
+    fn profiles() -> Vec<ProfileSummary> {
+        vec![
+            ProfileSummary {
+                name: "dev".to_string(),
+                kind: ProfileKind::Static,
+                region: Some("eu-west-1".to_string()),
+                account: None,
+                file: PathBuf::from("/home/user/.aws/config"),
+                line: 1,
+                origin: Origin::User,
+            },
+            ProfileSummary {
+                name: "admin, eu".to_string(),
+                kind: ProfileKind::Role,
+                region: None,
+                account: Some("222222222222".to_string()),
+                file: PathBuf::from("/home/user/.aws/config"),
+                line: 12,
+                origin: Origin::Shared,
+            },
+        ]
+    }
+
+    fn columns(columns: &[&str]) -> Vec<String> {
+        columns.iter().map(|column| column.to_string()).collect()
+    }
+
+    #[test]
+    fn to_table_should_align_columns_under_header() {
+        let result = to_table(&profiles(), &columns(&["name", "region", "line"]));
+
+        assert_eq!(
+            result,
+            "NAME       REGION     LINE\n\
+             dev        eu-west-1  1\n\
+             admin, eu  -          12\n"
+        );
+    }
+
+    #[test]
+    fn to_csv_should_quote_values_with_comma() {
+        let result = to_csv(&profiles(), &columns(&["name", "type", "account"]));
+
+        assert_eq!(
+            result,
+            "name,type,account\ndev,static,\n\"admin, eu\",role,222222222222\n"
+        );
+    }
+
+    #[test]
+    fn to_json_should_keep_only_given_columns() {
+        let result = to_json(&profiles(), &columns(&["name", "account", "line"]));
+
+        let rows: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(rows[0]["name"], "dev");
+        assert_eq!(rows[0]["account"], Value::Null);
+        assert_eq!(rows[1]["line"], 12);
+        assert!(rows[1].get("region").is_none());
+    }
+}
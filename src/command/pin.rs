@@ -0,0 +1,72 @@
+use std::process;
+
+use awsp::file::config::create_profile_list_from;
+use awsp::file::credential::get_aws_credentials_file_path;
+use awsp::file::helper::line::Sections;
+use awsp::file::layers::LayeredConfig;
+use awsp::pin::{load_pins, pin, unpin};
+
+pub fn run_pin(profile: Option<&str>, lenient: bool) {
+    let profile = match profile {
+        Some(profile) => profile,
+        None => {
+            let pins = load_pins();
+            if pins.is_empty() {
+                yellow_ln!("\nNo pinned profile\n");
+            }
+            for pinned in pins {
+                println!("{}", pinned);
+            }
+            return;
+        }
+    };
+
+    if !known_profiles(lenient).iter().any(|known| known == profile) {
+        red_ln!("\nNo profile named {}\n", profile);
+        process::exit(1);
+    }
+
+    match pin(profile) {
+        Ok(true) => {
+            green_ln!("Pinned {}", profile);
+        }
+        Ok(false) => {
+            yellow_ln!("{} is already pinned", profile);
+        }
+        Err(e) => {
+            e_red_ln!("Failed to pin {}: {}", profile, e);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn run_unpin(profile: &str) {
+    match unpin(profile) {
+        Ok(true) => {
+            green_ln!("Unpinned {}", profile);
+        }
+        Ok(false) => {
+            yellow_ln!("{} is not pinned", profile);
+        }
+        Err(e) => {
+            e_red_ln!("Failed to unpin {}: {}", profile, e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Profiles of the config files and the credentials file.
+fn known_profiles(lenient: bool) -> Vec<String> {
+    let config_profiles = LayeredConfig::load_default(Sections::of_config_file(lenient))
+        .ok()
+        .map(|config| config.profile_names());
+    let credentials_profiles = get_aws_credentials_file_path().ok().and_then(|location| {
+        create_profile_list_from(location.as_path(), Sections::of_credentials_file(lenient))
+    });
+
+    vec![config_profiles, credentials_profiles]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .collect()
+}
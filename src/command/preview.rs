@@ -0,0 +1,164 @@
+use std::path::PathBuf;
+use std::process;
+
+use awsp::file::diff::{diff_lines, DiffLine};
+use awsp::file::document::Document;
+use awsp::file::helper::line::{extract_config_from, is_comment, Sections};
+use awsp::file::layers::refresh_merged_config;
+use awsp::file::safe_write::{new_backup_id, write_file_safely};
+use awsp::profile::mask_secret;
+use chrono::Utc;
+use dialoguer::{theme::ColorfulTheme, Confirm};
+
+/// unchanged lines shown around each change
+const CONTEXT_LINES: usize = 2;
+const SKIPPED_LINES_MARK: &str = "  ...";
+
+/// A file awsp is about to rewrite.
+pub struct PendingWrite {
+    pub path: PathBuf,
+    pub before: Document,
+    pub after: Document,
+}
+
+/// Print the diff of every changed file and save them once the user agrees.
+/// `config_sections` is how the run reads the config file, the merged config is rebuilt alike.
+///
+/// # Return value
+///
+/// `false` - If nothing changed or the user declined
+pub fn confirm_and_save(writes: &[PendingWrite], config_sections: Sections) -> bool {
+    let changed = writes
+        .iter()
+        .filter(|write| write.before != write.after)
+        .collect::<Vec<&PendingWrite>>();
+    if changed.is_empty() {
+        yellow_ln!("\nNothing to change\n");
+        return false;
+    }
+
+    for write in &changed {
+        print_diff(write);
+    }
+    println!();
+
+    let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Save these changes?")
+        .default(false)
+        .interact()
+        .unwrap_or(false);
+    if !confirmed {
+        yellow_ln!("\nNothing saved\n");
+        return false;
+    }
+
+    let backup_id = new_backup_id(Utc::now());
+    let mut backed_up = false;
+    for write in changed {
+        let content = write.after.to_string();
+        let expected = write.before.to_string();
+        match write_file_safely(&write.path, &content, Some(&expected), &backup_id) {
+            Ok(backup) => backed_up |= backup.is_some(),
+            Err(e) => {
+                e_red_ln!("Failed to write {}: {}", write.path.display(), e);
+                process::exit(1);
+            }
+        }
+    }
+    if let Err(e) = refresh_merged_config(config_sections) {
+        e_yellow_ln!("Failed to update the merged config: {}", e);
+    }
+    if backed_up {
+        println!("Undo with: awsp backup restore {}", backup_id);
+    }
+    true
+}
+
+/// Print the path of the file and the changed lines, secrets masked.
+pub fn print_diff(write: &PendingWrite) {
+    println!("\n{}", write.path.display());
+    for line in diff_with_context(&write.before.to_string(), &write.after.to_string()) {
+        match line.chars().next() {
+            Some('+') => {
+                green_ln!("{}", line);
+            }
+            Some('-') => {
+                red_ln!("{}", line);
+            }
+            _ => println!("{}", line),
+        }
+    }
+}
+
+/// `+ line`, `- line` and `  line` of the changes with a few lines around them,
+/// `  ...` where unchanged lines are left out. Secrets are masked.
+fn diff_with_context(before: &str, after: &str) -> Vec<String> {
+    let diff = diff_lines(before, after);
+    let near_change = |index: usize| {
+        let from = index.saturating_sub(CONTEXT_LINES);
+        let to = (index + CONTEXT_LINES + 1).min(diff.len());
+        diff[from..to].iter().any(DiffLine::is_change)
+    };
+
+    let mut lines = Vec::new();
+    let mut skipped = false;
+    for (index, line) in diff.iter().enumerate() {
+        if !near_change(index) {
+            skipped = true;
+            continue;
+        }
+        if skipped && !lines.is_empty() {
+            lines.push(SKIPPED_LINES_MARK.to_string());
+        }
+        skipped = false;
+
+        lines.push(match line {
+            DiffLine::Same(text) => format!("  {}", masked(text)),
+            DiffLine::Removed(text) => format!("- {}", masked(text)),
+            DiffLine::Added(text) => format!("+ {}", masked(text)),
+        });
+    }
+
+    lines
+}
+
+fn masked(line: &str) -> String {
+    let trimmed = line.trim();
+    if is_comment(trimmed) || !trimmed.contains(['=', ':']) {
+        return line.to_string();
+    }
+
+    let (key, value) = extract_config_from(trimmed);
+    let masked_value = mask_secret(&key.to_lowercase(), value);
+    if masked_value == value {
+        return line.to_string();
+    }
+    let indent = &line[..line.len() - line.trim_start().len()];
+    format!("{}{} = {}", indent, key, masked_value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_with_context_should_leave_out_far_unchanged_lines() {
+        let before = "[a]\nk = 1\n\n[b]\nk = 2\n\n[c]\nk = 3\n";
+        let after = "[a]\nk = 10\n\n[b]\nk = 2\n\n[c]\nk = 3\naws_secret_access_key = x\n";
+
+        assert_eq!(
+            diff_with_context(before, after),
+            vec![
+                "  [a]",
+                "- k = 1",
+                "+ k = 10",
+                "  ",
+                "  [b]",
+                "  ...",
+                "  [c]",
+                "  k = 3",
+                "+ aws_secret_access_key = ****",
+            ]
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use awsp::session::{countdown_label, Session};
+use chrono::{DateTime, Utc};
+
+const STARSHIP_PRESET: &str = r#"# ~/.config/starship.toml
+[custom.awsp]
+command = "awsp prompt"
+when = 'test -n "$AWS_PROFILE"'
+format = "[☁ $output]($style) "
+style = "bold yellow"
+"#;
+
+const P10K_PRESET: &str = r#"# ~/.p10k.zsh, then add awsp to POWERLEVEL9K_RIGHT_PROMPT_ELEMENTS
+function prompt_awsp() {
+  [[ -n $AWS_PROFILE ]] || return
+  p10k segment -f 208 -t "$(awsp prompt)"
+}
+"#;
+
+const PS1_PRESET: &str = r#"# ~/.bashrc
+PS1='$(awsp prompt --format "[{profile}@{region}{expiry}] ")'"$PS1"
+"#;
+
+pub fn run(format: &str, preset: Option<&str>) {
+    match preset {
+        Some("starship") => print!("{}", STARSHIP_PRESET),
+        Some("p10k") => print!("{}", P10K_PRESET),
+        Some(_) => print!("{}", PS1_PRESET),
+        // no newline, the prompt places it
+        None => {
+            if let Some(session) = Session::current() {
+                print!("{}", render_prompt(format, &session, Utc::now()));
+            }
+        }
+    }
+}
+
+/// Fill `{profile}`, `{region}`, `{account}` and `{expiry}` in `format`.
+/// `{expiry}` is ` (42m)` with temporary credentials and empty otherwise, like unknown values.
+fn render_prompt(format: &str, session: &Session, now: DateTime<Utc>) -> String {
+    let expiry = session
+        .expires_in(now)
+        .map(|left| format!(" ({})", countdown_label(left)))
+        .unwrap_or_default();
+
+    format
+        .replace("{profile}", &session.profile)
+        .replace("{region}", session.region.as_deref().unwrap_or_default())
+        .replace("{account}", session.account.as_deref().unwrap_or_default())
+        .replace("{expiry}", &expiry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(expiry: Option<&str>) -> Session {
+        Session {
+            profile: "prod".to_string(),
+            region: Some("eu-west-1".to_string()),
+            account: None,
+            expiry: expiry.and_then(|expiry| expiry.parse().ok()),
+        }
+    }
+
+    #[test]
+    fn render_prompt_should_count_down_temporary_credentials() {
+        let now = "2021-01-01T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let format = "{profile}@{region}{expiry}";
+
+        assert_eq!(
+            render_prompt(format, &session(Some("2021-01-01T10:42:00Z")), now),
+            "prod@eu-west-1 (42m)"
+        );
+        assert_eq!(
+            render_prompt(format, &session(Some("2021-01-01T09:00:00Z")), now),
+            "prod@eu-west-1 (expired)"
+        );
+        assert_eq!(render_prompt(format, &session(None), now), "prod@eu-west-1");
+        assert_eq!(
+            render_prompt("{account}|{profile}", &session(None), now),
+            "|prod"
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use std::env;
+use std::process;
+
+use awsp::file::credential::get_aws_credentials_file_path;
+use awsp::file::helper::line::Sections;
+use awsp::file::layers::LayeredConfig;
+use awsp::sts::{expected_account_id_from, get_caller_identity, unsupported_credential_source_of};
+
+const AWS_DEFAULT_PROFILE: &str = "AWS_PROFILE";
+const DEFAULT_PROFILE_NAME: &str = "default";
+
+pub fn run(profile: Option<&str>, endpoint: Option<&str>, lenient: bool) {
+    let profile = profile
+        .map(String::from)
+        .or_else(|| env::var(AWS_DEFAULT_PROFILE).ok())
+        .unwrap_or_else(|| DEFAULT_PROFILE_NAME.to_string());
+
+    if !verify(&profile, endpoint, lenient) {
+        process::exit(1);
+    }
+}
+
+/// Print the caller identity of `profile`.
+/// Return `false` when it cannot be resolved or lands in another account than the profile expects,
+/// also for profiles whose credentials awsp cannot resolve: not verified is no pass.
+pub fn verify(profile: &str, endpoint: Option<&str>, lenient: bool) -> bool {
+    let config_map = LayeredConfig::load_default(Sections::of_config_file(lenient))
+        .map(|config| config.to_map())
+        .unwrap_or_default();
+    if let Some(source) = unsupported_credential_source_of(profile, &config_map) {
+        e_red_ln!(
+            " ! {} uses {} credentials, which awsp cannot resolve: not verified",
+            profile,
+            source
+        );
+        return false;
+    }
+    let credentials_file_path = match get_aws_credentials_file_path() {
+        Ok(path) => path,
+        Err(e) => {
+            e_red_ln!(" ! {}", e.message);
+            return false;
+        }
+    };
+
+    match get_caller_identity(profile, &config_map, &credentials_file_path, endpoint) {
+        Ok(identity) => {
+            print!(" Account: ");
+            green_ln!("{}", identity.account);
+            print!(" Arn:     ");
+            green_ln!("{}", identity.arn);
+            print!(" UserId:  ");
+            green_ln!("{}", identity.user_id);
+
+            let expected_account = config_map.get(profile).and_then(expected_account_id_from);
+            if identity.matches(expected_account.as_deref()) {
+                return true;
+            }
+
+            e_red_ln!(
+                " ! Account mismatch: profile {} expects {}",
+                profile,
+                expected_account.unwrap_or_default()
+            );
+            false
+        }
+        Err(e) => {
+            e_red_ln!(
+                " ! Failed to get caller identity of {}: {}",
+                profile,
+                e.message
+            );
+            false
+        }
+    }
+}
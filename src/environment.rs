@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use crate::settings::Settings;
+
+const AWSP_ENV_KEY: &str = "awsp_env";
+
+/// Profiles named like `pattern` belong to `environment`, from the `[environments]` section of awsp settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvironmentRule {
+    pub environment: String,
+    /// profile name, `*` matches any characters
+    pub pattern: String,
+}
+
+impl EnvironmentRule {
+    pub fn matches(&self, profile: &str) -> bool {
+        let pattern = self
+            .pattern
+            .split('*')
+            .map(regex::escape)
+            .collect::<Vec<String>>()
+            .join(".*");
+
+        Regex::new(&format!("^{}$", pattern))
+            .map(|regex| regex.is_match(profile))
+            .unwrap_or(false)
+    }
+}
+
+/// Environment of `profile`, its `awsp_env` key first, then the first matching rule.
+/// Rules of protected environments are tried first so that an ambiguous name stays protected.
+///
+/// # Return value
+///
+/// `None` - If the profile belongs to no environment
+pub fn environment_of(
+    profile: &str,
+    profile_config: Option<&HashMap<String, String>>,
+    settings: &Settings,
+) -> Option<String> {
+    if let Some(environment) = profile_config.and_then(|config| config.get(AWSP_ENV_KEY)) {
+        return Some(environment.to_lowercase());
+    }
+
+    let mut rules = settings.environment_rules.iter().collect::<Vec<_>>();
+    rules.sort_by_key(|rule| !is_protected(Some(&rule.environment), settings));
+
+    rules
+        .into_iter()
+        .find(|rule| rule.matches(profile))
+        .map(|rule| rule.environment.clone())
+}
+
+/// Whether switching to `environment` needs a confirmation.
+pub fn is_protected(environment: Option<&str>, settings: &Settings) -> bool {
+    environment.is_some_and(|environment| {
+        settings
+            .protected_environments
+            .iter()
+            .any(|protected| protected.eq_ignore_ascii_case(environment))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn settings() -> Settings {
+        Settings::from_file(Path::new("tests/sample-data/awsp_settings"))
+    }
+
+    #[test]
+    fn environment_of_should_prefer_awsp_env_key() {
+        let mut config = HashMap::new();
+        config.insert(AWSP_ENV_KEY.to_string(), "Staging".to_string());
+
+        assert_eq!(
+            environment_of("team-prod", Some(&config), &settings()),
+            Some("staging".to_string())
+        );
+    }
+
+    #[test]
+    fn environment_of_should_match_name_patterns_protected_first() {
+        let settings = settings();
+
+        assert_eq!(
+            environment_of("team-prod", None, &settings),
+            Some("prod".to_string())
+        );
+        assert_eq!(
+            environment_of("dev-prod", None, &settings),
+            Some("prod".to_string())
+        );
+        assert_eq!(
+            environment_of("dev-sandbox", None, &settings),
+            Some("dev".to_string())
+        );
+        assert_eq!(environment_of("local", None, &settings), None);
+    }
+
+    #[test]
+    fn is_protected_should_ignore_case() {
+        let settings = Settings::default();
+
+        assert!(is_protected(Some("PROD"), &settings));
+        assert!(!is_protected(Some("dev"), &settings));
+        assert!(!is_protected(None, &settings));
+    }
+}
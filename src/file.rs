@@ -1,12 +1,18 @@
+pub mod cache;
 pub mod config;
 pub mod credential;
+pub mod diff;
+pub mod document;
 pub mod helper;
+pub mod home;
+pub mod layers;
+pub mod safe_write;
 
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
 
-fn create_file_reader_for(file_path: &Path) -> BufReader<File> {
+pub(crate) fn create_file_reader_for(file_path: &Path) -> BufReader<File> {
     let file = File::open(file_path).unwrap_or_else(|_| {
         panic!(
             "Failed to open file, path: {}",
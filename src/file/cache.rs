@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use dirs::home_dir;
+use serde_json::Value;
+
+use crate::file::config::ProfileConfigMap;
+use crate::sts::account_id_from_arn;
+
+const AWS_DIR: &str = ".aws";
+const SSO_CACHE_DIR: &str = "sso/cache";
+const CLI_CACHE_DIR: &str = "cli/cache";
+const SSO_SESSION_SECTION_PREFIX: &str = "sso-session ";
+/// keys some tools write next to temporary keys in the credentials file
+const EXPIRATION_KEYS: &[&str] = &["aws_expiration", "x_security_token_expires", "expiration"];
+
+/// `~/.aws`, where the AWS CLI and SDKs cache credentials.
+pub fn get_aws_dir_path() -> Option<PathBuf> {
+    home_dir().map(|home| home.join(AWS_DIR))
+}
+
+/// Latest expiry of credentials cached for a profile, where one can be found:
+/// an expiration key of the profile, the SSO token cache for its start URL
+/// or the CLI cache of its role.
+///
+/// # Argument
+///
+/// `profile_config` - keys of the profile from both config and credentials file
+/// `config_map` - profile -> configs map of the config file, for `sso-session` sections
+/// `aws_dir_path` - directory holding `sso/cache` and `cli/cache`
+pub fn cached_expiry_of(
+    profile_config: &HashMap<String, String>,
+    config_map: &ProfileConfigMap,
+    aws_dir_path: &Path,
+) -> Option<DateTime<Utc>> {
+    if let Some(expiry) = EXPIRATION_KEYS
+        .iter()
+        .find_map(|key| profile_config.get(*key))
+        .and_then(|value| parse_timestamp(value))
+    {
+        return Some(expiry);
+    }
+
+    if let Some(role_arn) = profile_config.get("role_arn") {
+        return cached_role_expiry(role_arn, &aws_dir_path.join(CLI_CACHE_DIR));
+    }
+
+    let start_url = profile_config.get("sso_start_url").or_else(|| {
+        let session = profile_config.get("sso_session")?;
+        config_map
+            .get(&format!("{}{}", SSO_SESSION_SECTION_PREFIX, session))?
+            .get("sso_start_url")
+    })?;
+    cached_files_in(&aws_dir_path.join(SSO_CACHE_DIR))
+        .filter(|cache| cache["startUrl"].as_str() == Some(start_url.as_str()))
+        .filter_map(|cache| parse_timestamp(cache["expiresAt"].as_str()?))
+        .max()
+}
+
+/// CLI cache entries are keyed by a hash of the call, match them on the assumed role instead:
+/// `arn:aws:sts::<account>:assumed-role/<role name>/<session>`.
+fn cached_role_expiry(role_arn: &str, cli_cache_dir_path: &Path) -> Option<DateTime<Utc>> {
+    let account = account_id_from_arn(role_arn)?;
+    let role_name = role_arn.rsplit('/').next()?;
+
+    cached_files_in(cli_cache_dir_path)
+        .filter(|cache| {
+            cache["AssumedRoleUser"]["Arn"].as_str().is_some_and(|arn| {
+                account_id_from_arn(arn).as_deref() == Some(account.as_str())
+                    && arn.split('/').nth(1) == Some(role_name)
+            })
+        })
+        .filter_map(|cache| parse_timestamp(cache["Credentials"]["Expiration"].as_str()?))
+        .max()
+}
+
+fn cached_files_in(cache_dir_path: &Path) -> impl Iterator<Item = Value> {
+    fs::read_dir(cache_dir_path)
+        .into_iter()
+        .flatten()
+        .map_while(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .filter_map(|content| serde_json::from_str(&content).ok())
+}
+
+/// RFC 3339, also the `2021-01-01T00:00:00UTC` form older CLI versions write.
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    value
+        .parse::<DateTime<Utc>>()
+        .ok()
+        .or_else(|| value.replace("UTC", "Z").parse::<DateTime<Utc>>().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    const AWS_DIR_PATH: &str = "tests/sample-data/aws_dir";
+
+    fn profile_config(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn expiry(
+        profile_config: &HashMap<String, String>,
+        config_map: &ProfileConfigMap,
+    ) -> Option<String> {
+        cached_expiry_of(profile_config, config_map, Path::new(AWS_DIR_PATH))
+            .map(|expiry| expiry.to_rfc3339())
+    }
+
+    #[test]
+    fn cached_expiry_of_should_read_expiration_key_of_profile() {
+        let config = profile_config(&[("aws_expiration", "2021-01-01T10:00:00Z")]);
+
+        assert_eq!(
+            expiry(&config, &ProfileConfigMap::new()),
+            Some("2021-01-01T10:00:00+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn cached_expiry_of_should_find_sso_token_by_start_url() {
+        let config = profile_config(&[("sso_start_url", "https://example.awsapps.com/start")]);
+
+        assert_eq!(
+            expiry(&config, &ProfileConfigMap::new()),
+            Some("2021-01-01T12:00:00+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn cached_expiry_of_should_follow_sso_session_section() {
+        let config = profile_config(&[("sso_session", "corp")]);
+        let mut config_map = ProfileConfigMap::new();
+        config_map.insert(
+            "sso-session corp".to_string(),
+            profile_config(&[("sso_start_url", "https://example.awsapps.com/start")]),
+        );
+
+        assert_eq!(
+            expiry(&config, &config_map),
+            Some("2021-01-01T12:00:00+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn cached_expiry_of_should_match_cli_cache_on_assumed_role() {
+        let admin = profile_config(&[("role_arn", "arn:aws:iam::222222222222:role/admin")]);
+        let other = profile_config(&[("role_arn", "arn:aws:iam::333333333333:role/admin")]);
+
+        assert_eq!(
+            expiry(&admin, &ProfileConfigMap::new()),
+            Some("2021-01-01T13:00:00+00:00".to_string())
+        );
+        assert_eq!(expiry(&other, &ProfileConfigMap::new()), None);
+    }
+}
@@ -7,24 +7,41 @@ use dirs::home_dir;
 use rusoto_credential::CredentialsError;
 
 use crate::file::create_file_reader_for;
-use crate::file::helper::line::{extract_config_from, is_comment_or_empty};
-use crate::file::helper::line::{get_profile_name_from, is_profile};
+use crate::file::helper::line::{get_profile_name_in, get_section_name_from};
+use crate::file::helper::line::{IniLine, IniReader, Sections};
+use crate::file::layers::get_merged_config_file_path;
 
-const AWS_CONFIG_FILE_ENV_VAR_NAME: &str = "AWS_CONFIG_FILE";
+pub const AWS_CONFIG_FILE_ENV_VAR_NAME: &str = "AWS_CONFIG_FILE";
+/// set by awsp next to `AWS_CONFIG_FILE` when it points that at the merged config
+pub const AWSP_USER_CONFIG_FILE_ENV_VAR_NAME: &str = "AWSP_USER_CONFIG_FILE";
 const DEFAULT_AWS_CONFIG_FILE_PATH: &str = ".aws/config";
 
+/// profile -> configs map
+pub type ProfileConfigMap = HashMap<String, HashMap<String, String>>;
+
 /// Default config file location:
-/// 1: if set and not empty, use the value from environment variable `AWS_CONFIG_FILE`
+/// 1: if set and not empty, use the value from environment variable `AWS_CONFIG_FILE`,
+///    unless awsp pointed it at the merged config, then the value of `AWSP_USER_CONFIG_FILE`
 /// 2. otherwise return `~/.aws/config` (Linux/Mac) resp. `%USERPROFILE%\.aws\config` (Windows)
 pub fn get_aws_config_file_path() -> Result<PathBuf, CredentialsError> {
     let env = try_get_env_variable_value_from(AWS_CONFIG_FILE_ENV_VAR_NAME);
     match env {
+        Some(path) if is_merged_config_file(Path::new(&path)) => {
+            match try_get_env_variable_value_from(AWSP_USER_CONFIG_FILE_ENV_VAR_NAME) {
+                Some(user_path) => Ok(PathBuf::from(user_path)),
+                None => get_default_aws_config_file_path(),
+            }
+        }
         Some(path) => Ok(PathBuf::from(path)),
         None => get_default_aws_config_file_path(),
     }
 }
 
-fn try_get_env_variable_value_from(env_variable_name: &str) -> Option<String> {
+fn is_merged_config_file(config_file_path: &Path) -> bool {
+    get_merged_config_file_path().is_ok_and(|merged| merged == config_file_path)
+}
+
+pub(crate) fn try_get_env_variable_value_from(env_variable_name: &str) -> Option<String> {
     match var(env_variable_name) {
         Ok(value) => {
             if value.is_empty() {
@@ -57,12 +74,14 @@ fn get_default_aws_config_file_path() -> Result<PathBuf, CredentialsError> {
 /// # Argument
 ///
 /// `config_file_path` - path to aws profile config
+/// `sections` - how the headers of the file name its sections
 ///
 /// # Return value
 ///
 /// `None` - If destination path is not a file otherwise return [profile -> configs] hashmap
 pub fn create_profile_config_map_from(
     config_file_path: &Path,
+    sections: Sections,
 ) -> Option<HashMap<String, HashMap<String, String>>> {
     if !config_file_path.is_file() {
         return None;
@@ -70,59 +89,95 @@ pub fn create_profile_config_map_from(
 
     let config_file_reader = create_file_reader_for(config_file_path);
 
-    _create_profile_config_map_from(config_file_reader)
+    _create_profile_config_map_from(config_file_reader, sections)
 }
 
-fn _create_profile_config_map_from(
-    config_file_reader: BufReader<File>,
-) -> Option<HashMap<String, HashMap<String, String>>> {
-    let result: (HashMap<String, HashMap<String, String>>, Option<String>) = config_file_reader
+/// Create the list of profile names of target file, in order of first appearance.
+/// Works for both config and credentials file, each read with its `sections`.
+///
+/// # Return value
+///
+/// `None` - If destination path is not a file
+pub fn create_profile_list_from(file_path: &Path, sections: Sections) -> Option<Vec<String>> {
+    create_profile_location_list_from(file_path, sections)
+        .map(|locations| locations.into_iter().map(|(profile, _)| profile).collect())
+}
+
+/// Same as `create_profile_list_from`, each profile with the 1-based line number of its first header.
+///
+/// # Return value
+///
+/// `None` - If destination path is not a file
+pub fn create_profile_location_list_from(
+    file_path: &Path,
+    sections: Sections,
+) -> Option<Vec<(String, usize)>> {
+    if !file_path.is_file() {
+        return None;
+    }
+
+    let mut reader = IniReader::new();
+    let locations = create_file_reader_for(file_path)
         .lines()
-        .filter_map(|line| try_get_config_line_from(line.ok()))
-        .fold(Default::default(), |(config_map, profile), line| {
-            if is_profile(&line) {
-                (config_map, get_profile_name_from(&line))
-            } else {
-                match extract_config_from(&line) {
-                    (key, value) if !key.is_empty() && !value.is_empty() => {
-                        let config_map = insert_config_to_correspond_profile(
-                            key.to_string(),
-                            value.to_string(),
-                            profile.clone(),
-                            config_map,
-                        );
-
-                        (config_map, profile)
-                    }
-                    _ => (config_map, profile),
+        .map_while(Result::ok)
+        .enumerate()
+        .filter_map(|(index, line)| match reader.read(&line) {
+            IniLine::Header(header) => Some((get_profile_name_in(&header, sections)?, index + 1)),
+            _ => None,
+        })
+        .fold(
+            Vec::new(),
+            |mut locations: Vec<(String, usize)>, (profile, line_no)| {
+                if !locations.iter().any(|(listed, _)| *listed == profile) {
+                    locations.push((profile, line_no));
                 }
-            }
-        });
-
-    Some(result.0)
-}
+                locations
+            },
+        );
 
-fn try_get_config_line_from(maybe_config_line: Option<String>) -> Option<String> {
-    maybe_config_line.filter(|line| {
-        let line = line.trim().to_owned();
-        !is_comment_or_empty(&line)
-    })
+    Some(locations)
 }
 
-fn insert_config_to_correspond_profile(
-    key: String,
-    value: String,
-    profile: Option<String>,
-    mut config_map: HashMap<String, HashMap<String, String>>,
-) -> HashMap<String, HashMap<String, String>> {
-    if let Some(current_profile_name) = profile {
-        let current_profile = config_map
-            .entry(current_profile_name)
-            .or_insert_with(HashMap::new);
-        (*current_profile).insert(key, value);
+fn _create_profile_config_map_from(
+    config_file_reader: BufReader<File>,
+    sections: Sections,
+) -> Option<HashMap<String, HashMap<String, String>>> {
+    let mut config_map: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut reader = IniReader::new();
+    let mut profile = None;
+    let mut last_key = None;
+
+    for line in config_file_reader.lines().map_while(Result::ok) {
+        match (reader.read(&line), &profile) {
+            (IniLine::Header(header), _) => {
+                profile = get_section_name_from(&header, sections);
+                last_key = None;
+            }
+            (IniLine::Entry { key, value }, Some(profile)) => {
+                config_map
+                    .entry(profile.clone())
+                    .or_default()
+                    .insert(key.clone(), value);
+                last_key = Some(key);
+            }
+            (IniLine::Continuation(continuation), Some(profile)) => {
+                if let Some(value) = last_key
+                    .as_ref()
+                    .and_then(|key| config_map.get_mut(profile)?.get_mut(key))
+                {
+                    value.push_str(&continuation);
+                }
+            }
+            _ => (),
+        }
+    }
+    // `key =` without continuation lines sets nothing
+    for config in config_map.values_mut() {
+        config.retain(|_, value| !value.is_empty());
     }
+    config_map.retain(|_, config| !config.is_empty());
 
-    config_map
+    Some(config_map)
 }
 
 #[cfg(test)]
@@ -138,8 +193,10 @@ mod tests {
     #[test]
     fn create_profile_config_map_from_should_create_profile_config_map_correctly_when_given_config_with_one_profile(
     ) {
-        let result =
-            super::create_profile_config_map_from(Path::new("tests/sample-data/default_config"));
+        let result = super::create_profile_config_map_from(
+            Path::new("tests/sample-data/default_config"),
+            Sections::Config,
+        );
         assert!(result.is_some());
         let profiles = result.unwrap();
         assert_eq!(profiles.len(), 1);
@@ -153,9 +210,10 @@ mod tests {
     #[test]
     fn create_profile_config_map_from_should_create_profile_config_map_correctly_when_given_config_with_multiple_profiles(
     ) {
-        let result = super::create_profile_config_map_from(Path::new(
-            "tests/sample-data/multiple_profile_config",
-        ));
+        let result = super::create_profile_config_map_from(
+            Path::new("tests/sample-data/multiple_profile_config"),
+            Sections::Config,
+        );
         assert!(result.is_some());
 
         let profiles = result.unwrap();
@@ -175,11 +233,13 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn create_profile_config_map_from_should_not_parse_comments_to_config_map_when_given_config_with_comments(
     ) {
-        let result = super::create_profile_config_map_from(Path::new(
-            "tests/sample-data/multiple_profile_config",
-        ));
+        let result = super::create_profile_config_map_from(
+            Path::new("tests/sample-data/multiple_profile_config"),
+            Sections::Config,
+        );
 
         let profiles = result.unwrap();
 
@@ -190,6 +250,133 @@ mod tests {
         assert_eq!(bar_profile.contains_key("comments"), false);
     }
 
+    #[test]
+    fn create_profile_list_from_should_return_profiles_in_file_order() {
+        let result = create_profile_list_from(
+            Path::new("tests/sample-data/multiple_profile_config"),
+            Sections::Config,
+        );
+
+        assert_eq!(
+            result,
+            Some(vec![
+                "default".to_string(),
+                "foo".to_string(),
+                "bar".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn create_profile_location_list_from_should_return_header_line_of_each_profile() {
+        let result = create_profile_location_list_from(
+            Path::new("tests/sample-data/multiple_profile_config"),
+            Sections::Config,
+        );
+
+        assert_eq!(
+            result,
+            Some(vec![
+                ("default".to_string(), 1),
+                ("foo".to_string(), 4),
+                ("bar".to_string(), 8)
+            ])
+        );
+    }
+
+    #[test]
+    fn create_profile_list_from_should_list_config_profiles_like_the_aws_cli() {
+        let file_path = Path::new("tests/sample-data/section_edge_cases_config");
+
+        assert_eq!(
+            create_profile_list_from(file_path, Sections::Config),
+            Some(vec![
+                "default".to_string(),
+                "dev".to_string(),
+                "spaced".to_string(),
+                "quoted name".to_string(),
+                "bar".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn create_profile_config_map_from_should_skip_sections_the_aws_cli_ignores() {
+        let result = create_profile_config_map_from(
+            Path::new("tests/sample-data/section_edge_cases_config"),
+            Sections::Config,
+        )
+        .unwrap();
+
+        let mut names = result.keys().map(String::as_str).collect::<Vec<&str>>();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            vec![
+                "bar",
+                "default",
+                "dev",
+                "quoted name",
+                "spaced",
+                "sso-session corp"
+            ]
+        );
+        assert_eq!(result["dev"].get(REGION), Some(&"eu-west-1".to_string()));
+        assert_eq!(result["bar"].get(REGION), Some(&"us-west-2".to_string()));
+    }
+
+    /// Each `.ini` of the corpus is read like its `.json`, written by Python's configparser
+    /// with `#` and `;` as inline comment prefixes.
+    #[test]
+    fn create_profile_config_map_from_should_read_the_conformance_corpus_like_configparser() {
+        let corpus = Path::new("tests/sample-data/ini_conformance");
+        let mut ini_paths = std::fs::read_dir(corpus)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "ini"))
+            .collect::<Vec<_>>();
+        ini_paths.sort();
+        assert!(!ini_paths.is_empty());
+
+        for ini_path in ini_paths {
+            let expected: HashMap<String, HashMap<String, String>> = serde_json::from_str(
+                &std::fs::read_to_string(ini_path.with_extension("json")).unwrap(),
+            )
+            .unwrap();
+
+            assert_eq!(
+                create_profile_config_map_from(&ini_path, Sections::Titles),
+                Some(expected),
+                "{}",
+                ini_path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn create_profile_list_from_should_keep_every_section_when_lenient() {
+        let result = create_profile_list_from(
+            Path::new("tests/sample-data/section_edge_cases_config"),
+            Sections::Lenient,
+        );
+
+        assert_eq!(
+            result,
+            Some(vec![
+                "default".to_string(),
+                "dev".to_string(),
+                "staging".to_string(),
+                "  spaced".to_string(),
+                "\"quoted name\"".to_string(),
+                "two words".to_string(),
+                "profilefoo".to_string(),
+                "sso-session corp".to_string(),
+                "preview".to_string(),
+                "profilefoo bar".to_string()
+            ])
+        );
+    }
+
     #[test]
     fn try_get_env_variable_value_from_should_return_none_when_given_not_exist_variable_name() {
         let result = try_get_env_variable_value_from(
@@ -230,35 +417,9 @@ mod tests {
 
     #[test]
     fn parse_config_file_should_return_none_when_given_path_is_not_exist() {
-        let result = create_profile_config_map_from(Path::new("some/nonsense/path"));
-
-        assert_eq!(result, None);
-    }
-
-    #[test]
-    fn try_get_config_line_from_should_return_none_when_given_line_is_comment() {
-        let input = Some("# comment".to_owned());
-
-        let result = try_get_config_line_from(input);
-
-        assert_eq!(result, None);
-    }
-
-    #[test]
-    fn try_get_config_line_from_should_return_none_when_given_line_is_empty() {
-        let input = Some("".to_owned());
-
-        let result = try_get_config_line_from(input);
+        let result =
+            create_profile_config_map_from(Path::new("some/nonsense/path"), Sections::Config);
 
         assert_eq!(result, None);
     }
-
-    #[test]
-    fn try_get_config_line_from_should_return_result_when_given_proper_config_line() {
-        let input = Some("someConfig".to_owned());
-
-        let result = try_get_config_line_from(input.clone());
-
-        assert_eq!(result, input);
-    }
 }
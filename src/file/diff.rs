@@ -0,0 +1,73 @@
+/// One line of a line diff.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+impl DiffLine<'_> {
+    pub fn is_change(&self) -> bool {
+        !matches!(self, DiffLine::Same(_))
+    }
+}
+
+/// Lines of `old` and `new` in order, with the longest common subsequence kept as `Same`.
+/// Quadratic, fine for config files.
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old = old.lines().collect::<Vec<&str>>();
+    let new = new.lines().collect::<Vec<&str>>();
+
+    // common[i][j]: length of the common subsequence of old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut diff = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            diff.push(DiffLine::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            diff.push(DiffLine::Removed(old[i]));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(new[j]));
+            j += 1;
+        }
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_lines_should_keep_common_lines() {
+        let old = "[dev]\nregion = eu-west-1\noutput = json\n";
+        let new = "[dev]\nregion = us-east-1\noutput = json\n\n[ci]\n";
+
+        assert_eq!(
+            diff_lines(old, new),
+            vec![
+                DiffLine::Same("[dev]"),
+                DiffLine::Removed("region = eu-west-1"),
+                DiffLine::Added("region = us-east-1"),
+                DiffLine::Same("output = json"),
+                DiffLine::Added(""),
+                DiffLine::Added("[ci]"),
+            ]
+        );
+    }
+}
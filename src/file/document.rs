@@ -0,0 +1,683 @@
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::file::helper::line::{get_section_name_from, split_comment};
+use crate::file::helper::line::{IniLine, IniReader, Sections};
+
+pub const BYTE_ORDER_MARK: char = '\u{feff}';
+pub const CONFIG_PROFILE_PREFIX: &str = "profile ";
+
+/// A config or credentials file kept line for line, so that an edit rewrites only the lines it
+/// touches: comments, blank lines, ordering and formatting of everything else are kept,
+/// as are CRLF line endings and a byte order mark.
+///
+/// Sections are addressed by the name the parsers give them with the `Sections` of the file:
+/// in the config file `[profile dev]` is `dev` and `[sso-session corp]` is `sso-session corp`.
+/// Sections the file's reader ignores cannot be addressed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    lines: Vec<String>,
+    trailing_newline: bool,
+    crlf: bool,
+    byte_order_mark: bool,
+    sections: Sections,
+}
+
+/// A `key = value` line of a section, with the continuation lines of its value.
+struct KeyLines {
+    /// lowercased
+    key: String,
+    /// continuation lines included
+    value: String,
+    lines: Range<usize>,
+}
+
+/// Lines of a `key = value` entry, the lines of a multi-line value indented as continuation lines.
+fn entry_lines(indent: &str, key: &str, value: &str, comment: &str) -> Vec<String> {
+    let mut value_lines = value.split('\n');
+    let first = format!(
+        "{}{} = {}",
+        indent,
+        key,
+        value_lines.next().unwrap_or_default()
+    );
+    let mut lines = vec![format!("{}{}", first.trim_end(), comment)];
+    lines.extend(value_lines.map(|line| match line {
+        "" => String::new(),
+        line => format!("{}    {}", indent, line),
+    }));
+    lines
+}
+
+/// `[profile <name>]` title of a profile in the config file, `[default]` and the titles of
+/// other sections like `[sso-session x]` stay as they are.
+pub fn config_section_title(name: &str) -> String {
+    let title = format!("[{}]", name);
+    if get_section_name_from(&title, Sections::Config).as_deref() == Some(name) {
+        name.to_string()
+    } else {
+        format!("{}{}", CONFIG_PROFILE_PREFIX, name)
+    }
+}
+
+impl Document {
+    pub fn parse(content: &str, sections: Sections) -> Document {
+        let without_mark = content.trim_start_matches(BYTE_ORDER_MARK);
+
+        Document {
+            lines: without_mark.lines().map(String::from).collect(),
+            trailing_newline: without_mark.is_empty() || without_mark.ends_with('\n'),
+            // the first line ending decides for the whole file
+            crlf: without_mark
+                .find('\n')
+                .is_some_and(|end| without_mark[..end].ends_with('\r')),
+            byte_order_mark: without_mark.len() != content.len(),
+            sections,
+        }
+    }
+
+    /// Write `\n` line endings and no byte order mark from now on.
+    pub fn normalize_encoding(&mut self) {
+        self.crlf = false;
+        self.byte_order_mark = false;
+    }
+
+    /// Document of the file at `file_path`, empty when there is no such file yet.
+    pub fn load(file_path: &Path, sections: Sections) -> io::Result<Document> {
+        match fs::read_to_string(file_path) {
+            Ok(content) => Ok(Document::parse(&content, sections)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Document::parse("", sections)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Section names in order of first appearance.
+    pub fn section_names(&self) -> Vec<String> {
+        self.sections()
+            .into_iter()
+            .fold(Vec::new(), |mut names, (name, _)| {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+                names
+            })
+    }
+
+    pub fn has_section(&self, section: &str) -> bool {
+        self.sections().iter().any(|(name, _)| name == section)
+    }
+
+    /// Value of `key` in `section`, the last one wins like in the parsers.
+    pub fn get(&self, section: &str, key: &str) -> Option<String> {
+        let key = key.to_lowercase();
+
+        self.entries(section)
+            .into_iter()
+            .rev()
+            .find(|(found, _)| *found == key)
+            .map(|(_, value)| value)
+    }
+
+    /// `key = value` pairs of every declaration of `section`, in file order.
+    /// Keys are lowercased and values span their continuation lines, like in the parsers.
+    pub fn entries(&self, section: &str) -> Vec<(String, String)> {
+        self.key_lines(section)
+            .into_iter()
+            .map(|key_lines| (key_lines.key, key_lines.value))
+            .collect()
+    }
+
+    /// Append `[title]` with `entries` at the end, after a blank line.
+    pub fn add_section(&mut self, title: &str, entries: &[(String, String)]) {
+        if self
+            .lines
+            .last()
+            .is_some_and(|line| !line.trim().is_empty())
+        {
+            self.lines.push(String::new());
+        }
+        self.lines.push(format!("[{}]", title));
+        for (key, value) in entries {
+            self.lines.extend(entry_lines("", key, value, ""));
+        }
+    }
+
+    /// Replace the value of `key` in `section` in place, keeping its indentation and trailing
+    /// comment, or add the key after the last key of the section.
+    ///
+    /// # Return value
+    ///
+    /// `false` - If there is no such section
+    pub fn set(&mut self, section: &str, key: &str, value: &str) -> bool {
+        let lowercase_key = key.to_lowercase();
+        if let Some(key_lines) = self
+            .key_lines(section)
+            .into_iter()
+            .rev()
+            .find(|key_lines| key_lines.key == lowercase_key)
+        {
+            let line = &self.lines[key_lines.lines.start];
+            let indent = &line[..line.len() - line.trim_start().len()];
+            let comment = match split_comment(line) {
+                (text, Some(start)) => {
+                    let spacing = &text[text.trim_end().len()..];
+                    format!("{}{}", spacing, &line[start..])
+                }
+                (_, None) => String::new(),
+            };
+            let new_lines = entry_lines(indent, key, value, &comment);
+            self.lines.splice(key_lines.lines, new_lines);
+            return true;
+        }
+
+        match self
+            .sections()
+            .into_iter()
+            .rev()
+            .find(|(name, _)| name == section)
+        {
+            Some((_, range)) => {
+                let new_lines = entry_lines("", key, value, "");
+                self.lines.splice(range.end..range.end, new_lines);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove every `key` line of `section`, with the continuation lines of its value.
+    ///
+    /// # Return value
+    ///
+    /// `false` - If the section has no such key
+    pub fn remove_key(&mut self, section: &str, key: &str) -> bool {
+        let key = key.to_lowercase();
+        let to_remove = self
+            .key_lines(section)
+            .into_iter()
+            .filter(|key_lines| key_lines.key == key)
+            .map(|key_lines| key_lines.lines)
+            .collect::<Vec<Range<usize>>>();
+
+        for lines in to_remove.iter().rev() {
+            self.lines.drain(lines.clone());
+        }
+        !to_remove.is_empty()
+    }
+
+    /// Remove every declaration of `section` with its keys, comments between its keys and
+    /// the blank lines left behind. Comments after its last key belong to the next section and stay.
+    ///
+    /// # Return value
+    ///
+    /// `false` - If there is no such section
+    pub fn remove_section(&mut self, section: &str) -> bool {
+        let ranges = self
+            .sections()
+            .into_iter()
+            .filter(|(name, _)| name == section)
+            .map(|(_, range)| range)
+            .collect::<Vec<Range<usize>>>();
+
+        for range in ranges.iter().rev() {
+            let start = range.start;
+            self.lines.drain(range.clone());
+            while start < self.lines.len()
+                && self.lines[start].trim().is_empty()
+                && (start == 0 || self.lines[start - 1].trim().is_empty())
+            {
+                self.lines.remove(start);
+            }
+        }
+        if self.lines.last().is_some_and(|line| line.trim().is_empty()) {
+            self.lines.pop();
+        }
+        !ranges.is_empty()
+    }
+
+    /// Change every header of `section` to `[new_title]`, keys and comments stay.
+    ///
+    /// # Return value
+    ///
+    /// `false` - If there is no such section
+    pub fn rename_section(&mut self, section: &str, new_title: &str) -> bool {
+        let headers = self
+            .sections()
+            .into_iter()
+            .filter(|(name, _)| name == section)
+            .map(|(_, range)| range.start)
+            .collect::<Vec<usize>>();
+
+        for &index in &headers {
+            self.lines[index] = format!("[{}]", new_title);
+        }
+        !headers.is_empty()
+    }
+
+    /// Change every `[title]` header to `[new_title]`, also of sections the file's reader ignores.
+    ///
+    /// # Return value
+    ///
+    /// `false` - If there is no such header
+    pub fn rename_title(&mut self, title: &str, new_title: &str) -> bool {
+        let header = format!("[{}]", title);
+        let headers = self
+            .read_lines()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, line)| *line == IniLine::Header(header.clone()))
+            .map(|(index, _)| index)
+            .collect::<Vec<usize>>();
+
+        for &index in &headers {
+            self.lines[index] = format!("[{}]", new_title);
+        }
+        !headers.is_empty()
+    }
+
+    /// Lines between the `begin` and `end` marker lines, `None` without either marker.
+    /// An error when the markers are not exactly one `begin` followed by one `end`, see `block_lines`.
+    pub fn block(&self, begin: &str, end: &str) -> Result<Option<Vec<String>>, String> {
+        Ok(self
+            .block_lines(begin, end)?
+            .map(|range| self.lines[range.start + 1..range.end - 1].to_vec()))
+    }
+
+    /// Replace the lines between the `begin` and `end` marker lines with `lines`, or append them
+    /// with the markers at the end after a blank line. Without `lines` the markers go too.
+    /// The document is left as it is when the markers do not pair, see `block_lines`.
+    pub fn set_block(&mut self, begin: &str, end: &str, lines: &[String]) -> Result<(), String> {
+        let mut new_lines = Vec::new();
+        if !lines.is_empty() {
+            new_lines.push(begin.to_string());
+            new_lines.extend(lines.iter().cloned());
+            new_lines.push(end.to_string());
+        }
+
+        match self.block_lines(begin, end)? {
+            Some(range) => {
+                let start = range.start;
+                self.lines.splice(range, new_lines);
+                if lines.is_empty() {
+                    while start < self.lines.len()
+                        && self.lines[start].trim().is_empty()
+                        && (start == 0 || self.lines[start - 1].trim().is_empty())
+                    {
+                        self.lines.remove(start);
+                    }
+                    if self.lines.last().is_some_and(|line| line.trim().is_empty()) {
+                        self.lines.pop();
+                    }
+                }
+            }
+            None if lines.is_empty() => (),
+            None => {
+                if self
+                    .lines
+                    .last()
+                    .is_some_and(|line| !line.trim().is_empty())
+                {
+                    self.lines.push(String::new());
+                }
+                self.lines.extend(new_lines);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lines from the `begin` marker line to the `end` marker line, both included, `None` when
+    /// neither is there. Any other count or order of the markers, like a `begin` left without
+    /// its `end` by a hand edit, is an error: which lines belong to the block is then a guess.
+    fn block_lines(&self, begin: &str, end: &str) -> Result<Option<Range<usize>>, String> {
+        let positions_of = |marker: &str| {
+            self.lines
+                .iter()
+                .enumerate()
+                .filter(|(_, line)| line.trim() == marker)
+                .map(|(index, _)| index)
+                .collect::<Vec<usize>>()
+        };
+        let line_numbers = |positions: &[usize]| {
+            positions
+                .iter()
+                .map(|index| (index + 1).to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+
+        match (positions_of(begin).as_slice(), positions_of(end).as_slice()) {
+            ([], []) => Ok(None),
+            ([start], [stop]) if start < stop => Ok(Some(*start..*stop + 1)),
+            (starts, stops) => Err(format!(
+                "expected one '{}' line followed by one '{}' line, found them at lines [{}] and [{}]",
+                begin,
+                end,
+                line_numbers(starts),
+                line_numbers(stops)
+            )),
+        }
+    }
+
+    /// Each line as the parsers read it.
+    fn read_lines(&self) -> Vec<IniLine> {
+        let mut reader = IniReader::new();
+
+        self.lines.iter().map(|line| reader.read(line)).collect()
+    }
+
+    /// Name and lines of each section, from the header to its last key or continuation line.
+    fn sections(&self) -> Vec<(String, Range<usize>)> {
+        let mut sections: Vec<(Option<String>, Range<usize>)> = Vec::new();
+
+        for (index, line) in self.read_lines().into_iter().enumerate() {
+            match line {
+                IniLine::Header(header) => {
+                    // ignored sections are kept until here, their keys must not join the section before
+                    sections.push((
+                        get_section_name_from(&header, self.sections),
+                        index..index + 1,
+                    ));
+                }
+                IniLine::Blank | IniLine::Comment => (),
+                IniLine::Entry { .. } | IniLine::Continuation(_) | IniLine::Invalid => {
+                    if let Some((_, range)) = sections.last_mut() {
+                        range.end = index + 1;
+                    }
+                }
+            }
+        }
+
+        sections
+            .into_iter()
+            .filter_map(|(name, range)| Some((name?, range)))
+            .collect()
+    }
+
+    /// The `key = value` lines of every declaration of `section`, in file order.
+    fn key_lines(&self, section: &str) -> Vec<KeyLines> {
+        let lines = self.read_lines();
+        let mut key_lines: Vec<KeyLines> = Vec::new();
+
+        for (_, range) in self
+            .sections()
+            .into_iter()
+            .filter(|(name, _)| name == section)
+        {
+            for index in range.skip(1) {
+                match &lines[index] {
+                    IniLine::Entry { key, value } => key_lines.push(KeyLines {
+                        key: key.clone(),
+                        value: value.clone(),
+                        lines: index..index + 1,
+                    }),
+                    IniLine::Continuation(continuation) => {
+                        if let Some(last) = key_lines.last_mut() {
+                            last.value.push_str(continuation);
+                            last.lines.end = index + 1;
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        key_lines
+    }
+}
+
+impl std::fmt::Display for Document {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let line_ending = if self.crlf { "\r\n" } else { "\n" };
+
+        if self.byte_order_mark {
+            write!(f, "{}", BYTE_ORDER_MARK)?;
+        }
+        write!(f, "{}", self.lines.join(line_ending))?;
+        if self.trailing_newline && !self.lines.is_empty() {
+            write!(f, "{}", line_ending)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMENTED_CONFIG_PATH: &str = "tests/sample-data/commented_config";
+
+    fn commented_config() -> Document {
+        Document::load(Path::new(COMMENTED_CONFIG_PATH), Sections::Config).unwrap()
+    }
+
+    #[test]
+    fn document_should_write_back_what_it_read() {
+        let content = fs::read_to_string(COMMENTED_CONFIG_PATH).unwrap();
+
+        assert_eq!(commented_config().to_string(), content);
+        assert_eq!(
+            Document::parse("[a]\nk = v", Sections::Titles).to_string(),
+            "[a]\nk = v"
+        );
+    }
+
+    #[test]
+    fn document_should_keep_crlf_and_byte_order_mark_until_normalized() {
+        let content = "\u{feff}[a]\r\nk = v\r\n";
+        let mut document = Document::parse(content, Sections::Titles);
+
+        assert!(document.set("a", "k", "w"));
+        assert_eq!(document.to_string(), "\u{feff}[a]\r\nk = w\r\n");
+
+        document.normalize_encoding();
+        assert_eq!(document.to_string(), "[a]\nk = w\n");
+    }
+
+    #[test]
+    fn set_should_replace_value_in_place_or_add_after_last_key() {
+        let mut document = commented_config();
+
+        assert!(document.set("dev", "region", "eu-central-1"));
+        assert!(document.set("dev", "output", "json"));
+        assert!(document.set("admin", "role_arn", "arn:aws:iam::222222222222:role/ops"));
+        assert!(!document.set("missing", "region", "eu-central-1"));
+
+        let expected = fs::read_to_string(COMMENTED_CONFIG_PATH)
+            .unwrap()
+            .replace(
+                "region = eu-west-1\n",
+                "region = eu-central-1\noutput = json\n",
+            )
+            .replace("role/admin", "role/ops");
+        assert_eq!(document.to_string(), expected);
+        assert_eq!(
+            document.get("dev", "region"),
+            Some("eu-central-1".to_string())
+        );
+    }
+
+    #[test]
+    fn set_and_remove_key_should_cover_inline_comments_and_continuation_lines() {
+        let mut document = Document::parse(
+            "[profile dev]\n\
+             Region = eu-west-1  # Ireland\n\
+             credential_process = helper\n    --profile dev\n\n\
+             s3 =\n  max_concurrent_requests = 20\n",
+            Sections::Config,
+        );
+
+        assert_eq!(
+            document.get("dev", "credential_process"),
+            Some("helper\n--profile dev".to_string())
+        );
+        assert!(document.set("dev", "region", "eu-central-1"));
+        assert!(document.set("dev", "credential_process", "helper"));
+        assert!(document.remove_key("dev", "s3"));
+
+        assert_eq!(
+            document.to_string(),
+            "[profile dev]\n\
+             region = eu-central-1  # Ireland\n\
+             credential_process = helper\n\n"
+        );
+    }
+
+    #[test]
+    fn remove_section_should_keep_comments_of_next_section() {
+        let mut document = commented_config();
+
+        assert!(document.remove_section("default"));
+        assert!(!document.remove_section("default"));
+
+        assert_eq!(
+            document.to_string(),
+            "# Managed by hand, keep the comments\n\
+             \n\
+             # developer sandbox\n\
+             [profile dev]\n\
+             region = eu-west-1\n\
+             # keys live in the credentials file\n\
+             \n\
+             [profile admin]\n  \
+             role_arn = arn:aws:iam::222222222222:role/admin\n\
+             source_profile = dev\n"
+        );
+    }
+
+    #[test]
+    fn remove_section_should_not_leave_trailing_blank_line() {
+        let mut document = commented_config();
+
+        assert!(document.remove_section("admin"));
+
+        assert!(document
+            .to_string()
+            .ends_with("# keys live in the credentials file\n"));
+    }
+
+    #[test]
+    fn rename_section_and_remove_key_should_keep_other_lines() {
+        let mut document = commented_config();
+
+        assert!(document.rename_section("dev", "profile sandbox"));
+        assert!(document.remove_key("default", "output"));
+        assert!(!document.remove_key("default", "output"));
+
+        let expected = fs::read_to_string(COMMENTED_CONFIG_PATH)
+            .unwrap()
+            .replace("[profile dev]", "[profile sandbox]")
+            .replace("output = json\n", "");
+        assert_eq!(document.to_string(), expected);
+        assert_eq!(
+            document.section_names(),
+            vec!["default", "sandbox", "admin"]
+        );
+    }
+
+    #[test]
+    fn document_should_not_address_sections_the_aws_cli_ignores() {
+        let mut document = Document::load(
+            Path::new("tests/sample-data/section_edge_cases_config"),
+            Sections::Config,
+        )
+        .unwrap();
+
+        assert_eq!(
+            document.section_names(),
+            vec![
+                "default",
+                "dev",
+                "spaced",
+                "quoted name",
+                "sso-session corp",
+                "bar"
+            ]
+        );
+        assert_eq!(
+            document.entries("dev"),
+            vec![("region".to_string(), "eu-west-1".to_string())]
+        );
+        assert!(!document.has_section("staging"));
+
+        assert!(document.rename_title("staging", "profile staging"));
+        assert_eq!(
+            document.get("staging", "region"),
+            Some("eu-west-2".to_string())
+        );
+    }
+
+    #[test]
+    fn add_section_should_append_after_blank_line() {
+        let mut document = Document::parse("[default]\nregion = us-east-1\n", Sections::Config);
+
+        document.add_section(
+            &config_section_title("ci"),
+            &[("region".to_string(), "eu-west-1".to_string())],
+        );
+
+        assert_eq!(
+            document.to_string(),
+            "[default]\nregion = us-east-1\n\n[profile ci]\nregion = eu-west-1\n"
+        );
+        assert_eq!(document.section_names(), vec!["default", "ci"]);
+    }
+
+    #[test]
+    fn set_block_should_replace_append_and_remove_lines_between_markers() {
+        let mut document = Document::parse("[default]\nregion = us-east-1\n", Sections::Config);
+        let lines = |content: &str| content.lines().map(String::from).collect::<Vec<String>>();
+
+        document
+            .set_block(
+                "# begin",
+                "# end",
+                &lines("[profile ci]\nregion = eu-west-1"),
+            )
+            .unwrap();
+        document.add_section("profile dev", &[]);
+        assert_eq!(
+            document.to_string(),
+            "[default]\nregion = us-east-1\n\n# begin\n[profile ci]\nregion = eu-west-1\n# end\n\n[profile dev]\n"
+        );
+
+        document
+            .set_block("# begin", "# end", &lines("[profile qa]"))
+            .unwrap();
+        assert_eq!(
+            document.block("# begin", "# end"),
+            Ok(Some(lines("[profile qa]")))
+        );
+        assert_eq!(document.section_names(), vec!["default", "qa", "dev"]);
+
+        document.set_block("# begin", "# end", &[]).unwrap();
+        assert_eq!(document.block("# begin", "# end"), Ok(None));
+        assert_eq!(
+            document.to_string(),
+            "[default]\nregion = us-east-1\n\n[profile dev]\n"
+        );
+    }
+
+    #[test]
+    fn block_should_refuse_markers_that_do_not_pair() {
+        let dangling =
+            "# begin\n[profile ci]\nregion = eu-west-1\n\n[profile dev]\nregion = us-east-1\n";
+        let twice = "# begin\n[profile ci]\n# end\n# begin\n[profile qa]\n# end\n";
+        let reversed = "# end\n[profile ci]\n# begin\n";
+
+        for content in [dangling, twice, reversed] {
+            let mut document = Document::parse(content, Sections::Config);
+
+            assert!(document.block("# begin", "# end").is_err(), "{}", content);
+            assert!(document.set_block("# begin", "# end", &[]).is_err());
+            assert_eq!(document.to_string(), content);
+        }
+        assert_eq!(
+            Document::parse(dangling, Sections::Config).block("# begin", "# end"),
+            Err("expected one '# begin' line followed by one '# end' line, found them at lines [1] and []".to_string())
+        );
+    }
+}
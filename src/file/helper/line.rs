@@ -1,5 +1,145 @@
 use regex::Regex;
 
+const DEFAULT_PROFILE: &str = "default";
+const PROFILE_KEYWORD: &str = "profile";
+/// sections of the config file the AWS CLI reads besides profiles
+const CONFIG_SECTION_PREFIXES: &[&str] = &["sso-session ", "services "];
+const COMMENT_PREFIXES: &[char] = &['#', ';'];
+const KEY_VALUE_DELIMITERS: &[char] = &['=', ':'];
+
+/// A line of an INI file as botocore's configparser reads it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IniLine {
+    /// nothing but whitespace
+    Blank,
+    /// a comment alone on its line, `#` or `;`
+    Comment,
+    /// `[title]`, without a trailing comment
+    Header(String),
+    /// `key = value` or `key: value`, the key lowercased and the value without a trailing comment
+    Entry { key: String, value: String },
+    /// a line indented deeper than the key before it, continuing its value: what to append to
+    /// the value, a line break for each blank line in between and one for itself included
+    Continuation(String),
+    /// neither header nor key, the AWS CLI refuses the file
+    Invalid,
+}
+
+/// Reads the lines of an INI file in order, since whether a line continues a value depends on
+/// the lines before it.
+#[derive(Debug, Default)]
+pub struct IniReader {
+    /// indentation of the last line that was not blank, a comment or a continuation
+    indent: usize,
+    /// whether that line was a key, whose value indented lines continue
+    in_entry: bool,
+    /// blank lines since, part of the value when a continuation line follows
+    blank_lines: usize,
+}
+
+impl IniReader {
+    pub fn new() -> IniReader {
+        IniReader::default()
+    }
+
+    pub fn read(&mut self, line: &str) -> IniLine {
+        let (text, comment) = split_comment(line);
+        let text = text.trim();
+        if text.is_empty() {
+            return match comment {
+                Some(_) => IniLine::Comment,
+                None => {
+                    self.blank_lines += 1;
+                    IniLine::Blank
+                }
+            };
+        }
+
+        let indent = line.len() - line.trim_start().len();
+        if self.in_entry && indent > self.indent {
+            let continuation = format!("{}{}", "\n".repeat(self.blank_lines + 1), text);
+            self.blank_lines = 0;
+            return IniLine::Continuation(continuation);
+        }
+        self.indent = indent;
+        self.blank_lines = 0;
+
+        if let Some(title) = text
+            .strip_prefix('[')
+            .and_then(|rest| rest.rfind(']').map(|end| &rest[..end]))
+            .filter(|title| !title.is_empty())
+        {
+            self.in_entry = false;
+            return IniLine::Header(format!("[{}]", title));
+        }
+        match text.split_once(KEY_VALUE_DELIMITERS) {
+            Some((key, value)) if !key.trim().is_empty() => {
+                self.in_entry = true;
+                IniLine::Entry {
+                    key: key.trim().to_lowercase(),
+                    value: value.trim().to_string(),
+                }
+            }
+            _ => IniLine::Invalid,
+        }
+    }
+}
+
+/// `line` without its comment, and where the comment starts: `#` or `;` starting the line
+/// or following whitespace.
+pub fn split_comment(line: &str) -> (&str, Option<usize>) {
+    let comment_start = line.char_indices().find_map(|(index, c)| {
+        let after_whitespace = line[..index]
+            .chars()
+            .next_back()
+            .is_none_or(char::is_whitespace);
+        (COMMENT_PREFIXES.contains(&c) && after_whitespace).then_some(index)
+    });
+
+    match comment_start {
+        Some(index) => (&line[..index], Some(index)),
+        None => (line, None),
+    }
+}
+
+/// Read back whole: on one line, without surrounding whitespace, and with no `#` or `;` starting
+/// it or following whitespace, which readers of inline comments, awsp among them, cut it at.
+pub fn is_valid_value(value: &str) -> bool {
+    !value.contains(['\n', '\r']) && value.trim() == value && split_comment(value).1.is_none()
+}
+
+/// How the headers of a file name its sections.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sections {
+    /// The config file as the AWS CLI reads it: `[default]` and `[profile foo]` are profiles,
+    /// `[sso-session x]` and `[services x]` keep their title, any other section is ignored.
+    Config,
+    /// The credentials file as the AWS CLI reads it, and awsp's own files: every section by its
+    /// title, so `[profile foo]` is a profile named `profile foo`.
+    Titles,
+    /// How awsp read both files before following the AWS CLI: `profile ` is dropped from any
+    /// title and every section is a profile.
+    Lenient,
+}
+
+impl Sections {
+    pub fn of_config_file(lenient: bool) -> Sections {
+        if lenient {
+            Sections::Lenient
+        } else {
+            Sections::Config
+        }
+    }
+
+    pub fn of_credentials_file(lenient: bool) -> Sections {
+        if lenient {
+            Sections::Lenient
+        } else {
+            Sections::Titles
+        }
+    }
+}
+
 /// check if given line is described profile
 pub fn is_profile(line: &str) -> bool {
     let profile_regex = new_profile_regex();
@@ -9,39 +149,89 @@ pub fn is_profile(line: &str) -> bool {
 
 pub fn get_profile_name_from(line: &str) -> Option<String> {
     let profile_regex = new_profile_regex();
-    let caps = profile_regex.captures(&line).unwrap();
+    let caps = profile_regex.captures(line).unwrap();
 
     caps.get(2).map(|value| value.as_str().to_string())
 }
 
-fn new_profile_regex() -> Regex {
-    Regex::new(r"^\[(profile )?([^\]]+)\]$").expect("Failed to compile regex")
+/// Name the header `line` gives its section, `None` for a section ignored by the reader of the file.
+pub fn get_section_name_from(line: &str, sections: Sections) -> Option<String> {
+    let title = line.strip_prefix('[')?.strip_suffix(']')?;
+
+    match sections {
+        Sections::Config if CONFIG_SECTION_PREFIXES.iter().any(|p| title.starts_with(p)) => {
+            Some(title.to_string())
+        }
+        Sections::Config => config_profile_name_of(title),
+        Sections::Titles if !title.is_empty() => Some(title.to_string()),
+        Sections::Titles => None,
+        Sections::Lenient => get_profile_name_from(line),
+    }
 }
 
-pub fn is_comment_or_empty(line: &str) -> bool {
-    line.is_empty() || is_comment(line)
+/// Name of the profile the header `line` declares, `None` for any other section.
+pub fn get_profile_name_in(line: &str, sections: Sections) -> Option<String> {
+    match sections {
+        Sections::Config => config_profile_name_of(line.strip_prefix('[')?.strip_suffix(']')?),
+        Sections::Titles | Sections::Lenient => get_section_name_from(line, sections),
+    }
 }
 
-pub fn is_comment(to_check: &str) -> bool {
-    to_check.starts_with('#')
+/// `default`, or the second word of a title starting with `profile` and made of two words, split
+/// the way a shell does. Like botocore, the first word is not checked: `[profilefoo bar]` is `bar`.
+fn config_profile_name_of(title: &str) -> Option<String> {
+    if title == DEFAULT_PROFILE {
+        return Some(title.to_string());
+    }
+    if !title.starts_with(PROFILE_KEYWORD) {
+        return None;
+    }
+
+    match split_words(title)?.as_slice() {
+        [_, name] => Some(name.clone()),
+        _ => None,
+    }
 }
 
-pub fn extract_config_from(line: &str) -> (&str, &str) {
-    let config_map = line
-        .splitn(2, '=')
-        .map(|value| value.trim())
-        .collect::<Vec<&str>>();
+/// Whitespace separated words, quotes grouping words, `None` for an unclosed quote.
+fn split_words(text: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
 
-    (config_map[0], config_map[1])
+    for c in text.chars() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return None;
+    }
+    words.extend(word);
+
+    Some(words)
 }
 
-pub fn extract_value_from(line: &str) -> Option<String> {
-    let (_key, value) = extract_config_from(line);
+fn new_profile_regex() -> Regex {
+    Regex::new(r"^\[(profile )?(.+)\]$").expect("Failed to compile regex")
+}
 
-    if value.is_empty() {
-        None
-    } else {
-        Some(value.into())
+pub fn is_comment(to_check: &str) -> bool {
+    to_check.trim_start().starts_with(COMMENT_PREFIXES)
+}
+
+/// Key and value of a `key = value` or `key: value` line, the value empty without a delimiter.
+pub fn extract_config_from(line: &str) -> (&str, &str) {
+    match line.split_once(KEY_VALUE_DELIMITERS) {
+        Some((key, value)) => (key.trim(), value.trim()),
+        None => (line.trim(), ""),
     }
 }
 
@@ -50,6 +240,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn is_profile_should_return_true_when_given_line_is_appropriate_profile_line() {
         let line = "[someProfile]";
 
@@ -57,6 +248,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn is_profile_should_return_false_when_given_line_is_not_appropriate_profile() {
         let line = "some random text]][[";
 
@@ -64,6 +256,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn is_comment_should_return_true_when_given_line_is_start_with_sharp() {
         let line = "# some comment";
 
@@ -71,9 +264,102 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn is_comment_should_return_false_when_given_line_is_not_start_with_sharp() {
         let line = "some one line text in file";
 
         assert_eq!(false, is_comment(line));
     }
+
+    #[test]
+    fn is_comment_should_return_true_when_given_line_is_start_with_semicolon() {
+        assert!(is_comment("  ; some comment"));
+    }
+
+    #[test]
+    fn ini_reader_should_strip_comments_after_whitespace_only() {
+        let mut reader = IniReader::new();
+
+        assert_eq!(reader.read("; some comment"), IniLine::Comment);
+        assert_eq!(reader.read(""), IniLine::Blank);
+        assert_eq!(
+            reader.read("[profile dev] # comment"),
+            IniLine::Header("[profile dev]".to_string())
+        );
+        assert_eq!(
+            reader.read("Region = us-east-1 ; comment"),
+            IniLine::Entry {
+                key: "region".to_string(),
+                value: "us-east-1".to_string()
+            }
+        );
+        assert_eq!(
+            reader.read("sso_start_url: https://corp.awsapps.com/start#/"),
+            IniLine::Entry {
+                key: "sso_start_url".to_string(),
+                value: "https://corp.awsapps.com/start#/".to_string()
+            }
+        );
+        assert_eq!(reader.read("someConfig"), IniLine::Invalid);
+    }
+
+    #[test]
+    fn ini_reader_should_continue_values_on_deeper_indented_lines() {
+        let mut reader = IniReader::new();
+
+        reader.read("[services local]");
+        assert_eq!(
+            reader.read("  s3 ="),
+            IniLine::Entry {
+                key: "s3".to_string(),
+                value: String::new()
+            }
+        );
+        assert_eq!(
+            reader.read("    endpoint_url = http://localhost:9000"),
+            IniLine::Continuation("\nendpoint_url = http://localhost:9000".to_string())
+        );
+        assert_eq!(reader.read(""), IniLine::Blank);
+        assert_eq!(reader.read("    # comment"), IniLine::Comment);
+        assert_eq!(
+            reader.read("    addressing_style = path"),
+            IniLine::Continuation("\n\naddressing_style = path".to_string())
+        );
+        assert!(matches!(
+            reader.read("  region = eu-west-1"),
+            IniLine::Entry { .. }
+        ));
+        assert!(matches!(reader.read("[profile dev]"), IniLine::Header(_)));
+        assert_eq!(
+            reader.read("    region = eu-west-1"),
+            IniLine::Entry {
+                key: "region".to_string(),
+                value: "eu-west-1".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn get_profile_name_in_should_read_config_headers_like_the_aws_cli() {
+        assert_eq!(
+            get_profile_name_in("[profile default]", Sections::Config),
+            Some("default".to_string())
+        );
+        assert_eq!(
+            get_profile_name_in("[profile \"unclosed]", Sections::Config),
+            None
+        );
+        assert_eq!(
+            get_profile_name_in("[sso-session corp]", Sections::Config),
+            None
+        );
+        assert_eq!(
+            get_section_name_from("[sso-session corp]", Sections::Config),
+            Some("sso-session corp".to_string())
+        );
+        assert_eq!(
+            get_profile_name_in("[profile dev]", Sections::Titles),
+            Some("profile dev".to_string())
+        );
+    }
 }
@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+
+use dirs::home_dir;
+use rusoto_credential::CredentialsError;
+
+use crate::file::config::try_get_env_variable_value_from;
+
+const AWSP_HOME_ENV_VAR_NAME: &str = "AWSP_HOME";
+const DEFAULT_AWSP_HOME_PATH: &str = ".awsp";
+
+/// awsp's own directory for settings and state, never read by the AWS CLI:
+/// 1: if set and not empty, use the value from environment variable `AWSP_HOME`
+/// 2. otherwise return `~/.awsp` (Linux/Mac) resp. `%USERPROFILE%\.awsp` (Windows)
+pub fn get_awsp_home_path() -> Result<PathBuf, CredentialsError> {
+    match try_get_env_variable_value_from(AWSP_HOME_ENV_VAR_NAME) {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => match home_dir() {
+            Some(home_path) => Ok(home_path.join(DEFAULT_AWSP_HOME_PATH)),
+            None => Err(CredentialsError::new("Failed to determine home directory.")),
+        },
+    }
+}
+
+/// Path of `file_name` inside awsp's own directory.
+pub fn get_awsp_file_path(file_name: &str) -> Result<PathBuf, CredentialsError> {
+    get_awsp_home_path().map(|home| home.join(file_name))
+}
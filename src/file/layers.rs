@@ -0,0 +1,580 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use rusoto_credential::CredentialsError;
+
+use crate::file::config::AWS_CONFIG_FILE_ENV_VAR_NAME;
+use crate::file::config::{create_profile_location_list_from, get_aws_config_file_path};
+use crate::file::config::{try_get_env_variable_value_from, ProfileConfigMap};
+use crate::file::document::{config_section_title, Document};
+use crate::file::helper::line::Sections;
+use crate::file::home::get_awsp_file_path;
+use crate::settings::Settings;
+
+const CONFIG_DIR_NAME: &str = "config.d";
+const MERGED_CONFIG_FILE_NAME: &str = "merged_config";
+/// read by the user only, the AWS CLI cannot write to it
+#[cfg(unix)]
+const MERGED_CONFIG_MODE: u32 = 0o400;
+
+/// Where a config file comes from, in order of precedence: a section of the user's own file
+/// replaces the same section of shared files, which replaces that of the system-wide file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Origin {
+    /// `/etc/awsp/config`, or `system_config` of the settings
+    System,
+    /// a file of the `config.d` directory, or `config_dir` of the settings
+    Shared,
+    /// `~/.aws/config`, or `$AWS_CONFIG_FILE`, the only one awsp writes to
+    User,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let origin = match self {
+            Origin::System => "system",
+            Origin::Shared => "shared",
+            Origin::User => "user",
+        };
+
+        write!(f, "{}", origin)
+    }
+}
+
+/// One of the files the config is merged from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigLayer {
+    pub path: PathBuf,
+    pub origin: Origin,
+}
+
+impl fmt::Display for ConfigLayer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.path.display(), self.origin)
+    }
+}
+
+/// Files of the config, lowest precedence first: the system-wide file when it exists, the files
+/// of the shared config directory in name order, later names winning, then `user_config_file_path`.
+/// Hidden files and editor backups ending with `~` in the directory are skipped.
+pub fn config_layers(user_config_file_path: &Path, settings: &Settings) -> Vec<ConfigLayer> {
+    let mut layers = Vec::new();
+    if settings.system_config.is_file() {
+        layers.push(ConfigLayer {
+            path: settings.system_config.clone(),
+            origin: Origin::System,
+        });
+    }
+
+    let config_dir = settings.config_dir.clone().unwrap_or_else(|| {
+        user_config_file_path
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(CONFIG_DIR_NAME)
+    });
+    let mut shared_files = fs::read_dir(&config_dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| !name.starts_with('.') && !name.ends_with('~'))
+                })
+                .collect::<Vec<PathBuf>>()
+        })
+        .unwrap_or_default();
+    shared_files.sort();
+    layers.extend(shared_files.into_iter().map(|path| ConfigLayer {
+        path,
+        origin: Origin::Shared,
+    }));
+
+    layers.push(ConfigLayer {
+        path: user_config_file_path.to_path_buf(),
+        origin: Origin::User,
+    });
+    layers
+}
+
+/// The config merged from its layers: each section comes whole from the layer of highest
+/// precedence declaring it, the same section of lower layers is shadowed.
+#[derive(Debug, Clone)]
+pub struct LayeredConfig {
+    /// lowest precedence first, the user's own file last
+    layers: Vec<ConfigLayer>,
+    documents: Vec<Document>,
+    sections: Sections,
+    /// section -> index in `layers` of the layer it comes from
+    owners: HashMap<String, usize>,
+}
+
+impl LayeredConfig {
+    /// Read every layer with `sections`, skipping files that cannot be read.
+    pub fn load(layers: Vec<ConfigLayer>, sections: Sections) -> LayeredConfig {
+        let documents = layers
+            .iter()
+            .map(|layer| {
+                Document::load(&layer.path, sections)
+                    .unwrap_or_else(|_| Document::parse("", sections))
+            })
+            .collect::<Vec<Document>>();
+        let mut owners = HashMap::new();
+        for (index, document) in documents.iter().enumerate() {
+            for name in document.section_names() {
+                owners.insert(name, index);
+            }
+        }
+
+        LayeredConfig {
+            layers,
+            documents,
+            sections,
+            owners,
+        }
+    }
+
+    /// The user's config file alone.
+    pub fn of_user_file(user_config_file_path: &Path, sections: Sections) -> LayeredConfig {
+        LayeredConfig::load(
+            vec![ConfigLayer {
+                path: user_config_file_path.to_path_buf(),
+                origin: Origin::User,
+            }],
+            sections,
+        )
+    }
+
+    /// Layers of the user's config file and of the shared and system-wide files of the settings.
+    pub fn load_default(sections: Sections) -> Result<LayeredConfig, CredentialsError> {
+        let user_config_file_path = get_aws_config_file_path()?;
+
+        Ok(LayeredConfig::load(
+            config_layers(&user_config_file_path, &Settings::load()),
+            sections,
+        ))
+    }
+
+    /// The user's own config file, the one to write to.
+    pub fn user_file_path(&self) -> &Path {
+        self.layers
+            .last()
+            .map(|layer| layer.path.as_path())
+            .expect("The user's config file is always a layer")
+    }
+
+    /// Whether any section comes from another file than the user's own.
+    pub fn has_shared_sections(&self) -> bool {
+        let user_layer = self.layers.len() - 1;
+
+        self.owners.values().any(|&owner| owner != user_layer)
+    }
+
+    /// Whether shells started by awsp read the merged config rather than the user's own file:
+    /// only when `export_merged_config` is set and shared sections exist. Otherwise
+    /// `AWS_CONFIG_FILE` stays the user's file, where `aws configure set` writes and awsp reads.
+    pub fn exports_merged_config(&self, settings: &Settings) -> bool {
+        settings.export_merged_config && self.has_shared_sections()
+    }
+
+    /// profile -> configs map of the merged sections, keys without value left out like in
+    /// `create_profile_config_map_from`.
+    pub fn to_map(&self) -> ProfileConfigMap {
+        self.owners
+            .iter()
+            .map(|(name, &owner)| {
+                let config = self.documents[owner]
+                    .entries(name)
+                    .into_iter()
+                    .filter(|(_, value)| !value.is_empty())
+                    .collect::<HashMap<String, String>>();
+                (name.clone(), config)
+            })
+            .filter(|(_, config)| !config.is_empty())
+            .collect()
+    }
+
+    /// Layer `section` comes from.
+    pub fn origin_of(&self, section: &str) -> Option<&ConfigLayer> {
+        self.owners.get(section).map(|&owner| &self.layers[owner])
+    }
+
+    /// `key = value` pairs of `section` in the file it comes from, in file order.
+    pub fn entries(&self, section: &str) -> Vec<(String, String)> {
+        self.owners
+            .get(section)
+            .map(|&owner| self.documents[owner].entries(section))
+            .unwrap_or_default()
+    }
+
+    /// Lower layers also declaring `section`, highest first.
+    pub fn shadowed_layers_of(&self, section: &str) -> Vec<&ConfigLayer> {
+        let owner = match self.owners.get(section) {
+            Some(&owner) => owner,
+            None => return Vec::new(),
+        };
+
+        (0..owner)
+            .rev()
+            .filter(|&index| self.documents[index].has_section(section))
+            .map(|index| &self.layers[index])
+            .collect()
+    }
+
+    /// Sections of shared and system-wide files, which awsp does not write to, where `key` is
+    /// `value`, with the layer each comes from, in the order of `profile_locations`.
+    pub fn shared_sections_with(&self, key: &str, value: &str) -> Vec<(String, &ConfigLayer)> {
+        let user_layer = self.layers.len() - 1;
+
+        self.listing_order()
+            .into_iter()
+            .filter(|&index| index != user_layer)
+            .flat_map(|index| {
+                self.documents[index]
+                    .section_names()
+                    .into_iter()
+                    .filter(move |name| {
+                        self.owners.get(name) == Some(&index)
+                            && self.documents[index].get(name, key).as_deref() == Some(value)
+                    })
+                    .map(move |name| (name, &self.layers[index]))
+            })
+            .collect()
+    }
+
+    /// Profiles with the file and 1-based header line they come from: those of the user's own
+    /// file first, then those of shared files in name order, then those of the system-wide file.
+    pub fn profile_locations(&self) -> Vec<(String, &Path, usize)> {
+        self.listing_order()
+            .into_iter()
+            .flat_map(|index| {
+                let layer = &self.layers[index];
+                create_profile_location_list_from(&layer.path, self.sections)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(move |(profile, _)| self.owners.get(profile) == Some(&index))
+                    .map(move |(profile, line)| (profile, layer.path.as_path(), line))
+            })
+            .collect()
+    }
+
+    /// Profile names in the order of `profile_locations`.
+    pub fn profile_names(&self) -> Vec<String> {
+        self.profile_locations()
+            .into_iter()
+            .map(|(profile, _, _)| profile)
+            .collect()
+    }
+
+    /// The merged sections as one config file for the AWS CLI, in the order of `profile_locations`.
+    pub fn render(&self) -> String {
+        let mut merged = Document::parse("", Sections::Config);
+
+        for index in self.listing_order() {
+            for name in self.documents[index].section_names() {
+                if self.owners.get(&name) == Some(&index) {
+                    merged.add_section(
+                        &config_section_title(&name),
+                        &self.documents[index].entries(&name),
+                    );
+                }
+            }
+        }
+
+        merged.to_string()
+    }
+
+    fn listing_order(&self) -> Vec<usize> {
+        let user_layer = self.layers.len() - 1;
+        let mut order = vec![user_layer];
+        order.extend(
+            self.layers
+                .iter()
+                .enumerate()
+                .filter(|(_, layer)| layer.origin == Origin::Shared)
+                .map(|(index, _)| index),
+        );
+        order.extend(
+            self.layers
+                .iter()
+                .position(|layer| layer.origin == Origin::System),
+        );
+        order
+    }
+}
+
+/// `~/.awsp/merged_config`, the merged config the AWS CLI reads in shells started by awsp.
+pub fn get_merged_config_file_path() -> Result<PathBuf, CredentialsError> {
+    get_awsp_file_path(MERGED_CONFIG_FILE_NAME)
+}
+
+/// Replace `merged_config_file_path` with the merged config, read-only and readable by the user
+/// only, so that an AWS CLI write meant for the user's file fails rather than being lost.
+pub fn write_merged_config(
+    config: &LayeredConfig,
+    merged_config_file_path: &Path,
+) -> io::Result<()> {
+    if let Some(parent) = merged_config_file_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut content = String::from("# Written by awsp from these files, changes here are lost\n");
+    for layer in config.layers.iter().rev() {
+        content.push_str(&format!("#   {}\n", layer));
+    }
+    content.push_str(&config.render());
+
+    // the file is read-only, it is replaced rather than written
+    let temp_path = merged_config_file_path.with_extension(format!("tmp-{}", process::id()));
+    let _ = fs::remove_file(&temp_path);
+    let written = create_read_only_file(&temp_path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .and_then(|_| fs::rename(&temp_path, merged_config_file_path));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    written
+}
+
+/// Rewrite the merged config after a change of the user's files when `AWS_CONFIG_FILE` points at
+/// it, so that the AWS CLI of this shell sees the change before the next switch. The files are
+/// read with `sections`, the way the run that changed them reads the config file.
+pub fn refresh_merged_config(sections: Sections) -> io::Result<()> {
+    let merged_config_file_path = match (
+        try_get_env_variable_value_from(AWS_CONFIG_FILE_ENV_VAR_NAME),
+        get_merged_config_file_path(),
+    ) {
+        (Some(path), Ok(merged)) if merged == Path::new(&path) => merged,
+        _ => return Ok(()),
+    };
+    let config = LayeredConfig::load_default(sections).map_err(|e| io::Error::other(e.message))?;
+
+    write_merged_config(&config, &merged_config_file_path)
+}
+
+#[cfg(unix)]
+fn create_read_only_file(file_path: &Path) -> io::Result<File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(MERGED_CONFIG_MODE)
+        .open(file_path)
+}
+
+#[cfg(not(unix))]
+fn create_read_only_file(file_path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(file_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::test_dir;
+
+    const LAYERED_DIR_PATH: &str = "tests/sample-data/layered";
+
+    fn layered_config() -> LayeredConfig {
+        let settings = Settings {
+            system_config: Path::new(LAYERED_DIR_PATH).join("system_config"),
+            ..Settings::default()
+        };
+
+        LayeredConfig::load(
+            config_layers(&Path::new(LAYERED_DIR_PATH).join("config"), &settings),
+            Sections::Config,
+        )
+    }
+
+    fn file_name_of(layer: &ConfigLayer) -> &str {
+        layer
+            .path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap()
+    }
+
+    #[test]
+    fn config_layers_should_put_system_then_shared_files_in_name_order_then_user_file() {
+        let config = layered_config();
+
+        let layers = config
+            .layers
+            .iter()
+            .map(|layer| (file_name_of(layer), layer.origin))
+            .collect::<Vec<(&str, Origin)>>();
+        assert_eq!(
+            layers,
+            vec![
+                ("system_config", Origin::System),
+                ("10-platform", Origin::Shared),
+                ("20-team", Origin::Shared),
+                ("config", Origin::User)
+            ]
+        );
+    }
+
+    #[test]
+    fn layered_config_should_take_each_section_whole_from_the_highest_layer() {
+        let config = layered_config();
+        let map = config.to_map();
+
+        assert_eq!(map["team"].len(), 1);
+        assert_eq!(map["team"]["region"], "eu-west-1");
+        assert_eq!(map["platform-admin"]["sso_role_name"], "ReadOnly");
+        assert_eq!(
+            map["sso-session corp"]["sso_start_url"],
+            "https://corp.awsapps.com/start"
+        );
+        assert_eq!(config.origin_of("team").map(file_name_of), Some("20-team"));
+        assert_eq!(
+            config
+                .shadowed_layers_of("team")
+                .into_iter()
+                .map(file_name_of)
+                .collect::<Vec<&str>>(),
+            vec!["10-platform", "system_config"]
+        );
+        assert!(config.has_shared_sections());
+    }
+
+    #[test]
+    fn profile_locations_should_list_user_file_first_then_shared_then_system() {
+        let config = layered_config();
+
+        let locations = config
+            .profile_locations()
+            .into_iter()
+            .map(|(profile, file, line)| (profile, file.file_name().unwrap().to_owned(), line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            locations,
+            vec![
+                ("default".to_string(), "config".into(), 1),
+                ("platform-admin".to_string(), "config".into(), 4),
+                ("team".to_string(), "20-team".into(), 1),
+                ("shared-readonly".to_string(), "system_config".into(), 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn shared_sections_with_should_skip_the_user_file_and_what_it_shadows() {
+        let config = layered_config();
+
+        let sections = config
+            .shared_sections_with("source_profile", "default")
+            .into_iter()
+            .map(|(name, layer)| (name, file_name_of(layer)))
+            .collect::<Vec<(String, &str)>>();
+        assert_eq!(
+            sections,
+            vec![("shared-readonly".to_string(), "system_config")]
+        );
+        assert!(config
+            .shared_sections_with("sso_session", "corp")
+            .is_empty());
+    }
+
+    #[test]
+    fn render_should_declare_each_merged_section_once() {
+        let config = layered_config();
+
+        let merged = Document::parse(&config.render(), Sections::Config);
+
+        let mut names = merged.section_names();
+        names.sort();
+        let mut expected = config.to_map().into_keys().collect::<Vec<String>>();
+        expected.sort();
+        assert_eq!(names, expected);
+        assert_eq!(merged.entries("team"), config.entries("team"));
+        assert!(!LayeredConfig::of_user_file(
+            &Path::new(LAYERED_DIR_PATH).join("config"),
+            Sections::Config
+        )
+        .has_shared_sections());
+    }
+
+    #[test]
+    fn cli_style_write_should_land_in_the_user_file_awsp_reads() {
+        let dir_path = test_dir("layers");
+        fs::create_dir(dir_path.join(CONFIG_DIR_NAME)).unwrap();
+        for file_name in ["config", "config.d/10-platform", "config.d/20-team"] {
+            fs::copy(
+                Path::new(LAYERED_DIR_PATH).join(file_name),
+                dir_path.join(file_name),
+            )
+            .unwrap();
+        }
+        let user_config_file_path = dir_path.join("config");
+        let settings = Settings {
+            system_config: dir_path.join("no_system_config"),
+            ..Settings::default()
+        };
+        let config = LayeredConfig::load(
+            config_layers(&user_config_file_path, &settings),
+            Sections::Config,
+        );
+        assert!(config.has_shared_sections());
+        assert!(!config.exports_merged_config(&settings));
+
+        // what `aws configure set region ap-northeast-1 --profile cli` does to AWS_CONFIG_FILE
+        let mut cli_file = OpenOptions::new()
+            .append(true)
+            .open(config.user_file_path())
+            .unwrap();
+        cli_file
+            .write_all(b"\n[profile cli]\nregion = ap-northeast-1\n")
+            .unwrap();
+
+        let reloaded = LayeredConfig::load(
+            config_layers(&user_config_file_path, &settings),
+            Sections::Config,
+        );
+        assert_eq!(reloaded.to_map()["cli"]["region"], "ap-northeast-1");
+        assert_eq!(
+            reloaded.origin_of("cli").map(|layer| layer.origin),
+            Some(Origin::User)
+        );
+
+        fs::remove_dir_all(&dir_path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_merged_config_should_replace_the_read_only_file_when_exported() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir_path = test_dir("layers");
+        let merged_config_file_path = dir_path.join(MERGED_CONFIG_FILE_NAME);
+        let config = layered_config();
+        let settings = Settings {
+            export_merged_config: true,
+            ..Settings::default()
+        };
+        assert!(config.exports_merged_config(&settings));
+
+        write_merged_config(&config, &merged_config_file_path).unwrap();
+        write_merged_config(&config, &merged_config_file_path).unwrap();
+
+        let mode = fs::metadata(&merged_config_file_path)
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, MERGED_CONFIG_MODE);
+        let merged = Document::load(&merged_config_file_path, Sections::Config).unwrap();
+        assert_eq!(merged.entries("team"), config.entries("team"));
+        assert_eq!(fs::read_dir(&dir_path).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir_path).unwrap();
+    }
+}
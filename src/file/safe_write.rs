@@ -0,0 +1,386 @@
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+/// next to the written files, `~/.aws/.awsp-backups` for the default ones
+pub const BACKUP_DIR_NAME: &str = ".awsp-backups";
+const LOCK_FILE_SUFFIX: &str = ".lock";
+const TEMP_FILE_SUFFIX: &str = ".awsp-tmp";
+const BACKUP_ID_FORMAT: &str = "%Y%m%dT%H%M%S%3fZ";
+/// backups kept per file, older ones are deleted
+const BACKUPS_KEPT: usize = 20;
+const LOCK_ATTEMPTS: u32 = 50;
+const LOCK_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Copy of a file taken right before awsp replaced it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backup {
+    /// shared by the files written together
+    pub id: String,
+    /// the file the backup was taken of
+    pub file_path: PathBuf,
+    pub backup_path: PathBuf,
+}
+
+/// Id of backups taken now, sorting in time order.
+pub fn new_backup_id(now: DateTime<Utc>) -> String {
+    now.format(BACKUP_ID_FORMAT).to_string()
+}
+
+/// Replace `file_path` with `content` so that a crash leaves either the old or the new file.
+/// A symlink is followed and the file it points to is replaced, the link stays as it is:
+///
+/// 1. lock `<backup dir>/<file name>.lock`, waiting a few seconds for other awsp processes
+/// 2. refuse when the file no longer holds `expected`, e.g. the AWS CLI wrote it meanwhile
+/// 3. copy the file to `<backup dir>/<backup id>.<file name>`
+/// 4. write a temp file next to it with the same permissions and owner, fsync it
+/// 5. rename the temp file over the file and fsync the directory
+///
+/// # Argument
+///
+/// `expected` - content the change was made from, `None` to overwrite whatever is there
+///
+/// # Return value
+///
+/// The backup taken, `None` if the file did not exist
+pub fn write_file_safely(
+    file_path: &Path,
+    content: &str,
+    expected: Option<&str>,
+    backup_id: &str,
+) -> io::Result<Option<Backup>> {
+    let file_path = &resolve_file_path(file_path)?;
+    let dir_path = parent_of(file_path);
+    let file_name = file_name_of(file_path)?;
+    let backup_dir_path = dir_path.join(BACKUP_DIR_NAME);
+    create_private_dir(&backup_dir_path)?;
+
+    let _lock = lock(&backup_dir_path.join(format!("{}{}", file_name, LOCK_FILE_SUFFIX)))?;
+
+    let current = match fs::read_to_string(file_path) {
+        Ok(current) => Some(current),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+    if let Some(expected) = expected {
+        if current.as_deref().unwrap_or_default() != expected {
+            return Err(io::Error::other(format!(
+                "{} changed since awsp read it, nothing written",
+                file_path.display()
+            )));
+        }
+    }
+
+    let backup = match current {
+        Some(_) => {
+            let backup_path = backup_dir_path.join(format!("{}.{}", backup_id, file_name));
+            fs::copy(file_path, &backup_path)?;
+            Some(Backup {
+                id: backup_id.to_string(),
+                file_path: file_path.to_path_buf(),
+                backup_path,
+            })
+        }
+        None => None,
+    };
+
+    let temp_path = dir_path.join(format!(
+        ".{}{}-{}",
+        file_name,
+        TEMP_FILE_SUFFIX,
+        process::id()
+    ));
+    // left behind by a crash of a process with the same id
+    let _ = fs::remove_file(&temp_path);
+    let written = write_temp_file(&temp_path, file_path, content)
+        .and_then(|_| fs::rename(&temp_path, file_path))
+        .and_then(|_| sync_dir(dir_path));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    written?;
+
+    prune_backups(&backup_dir_path, file_name);
+    Ok(backup)
+}
+
+/// The file `file_path` is or points to, which is what `write_file_safely` replaces and where
+/// it keeps the backups. The path itself when there is no file yet.
+pub fn resolve_file_path(file_path: &Path) -> io::Result<PathBuf> {
+    match fs::canonicalize(file_path) {
+        Ok(resolved) => Ok(resolved),
+        Err(e)
+            if e.kind() == io::ErrorKind::NotFound && fs::symlink_metadata(file_path).is_err() =>
+        {
+            Ok(file_path.to_path_buf())
+        }
+        Err(e) => Err(io::Error::new(
+            e.kind(),
+            format!("{}: {}", file_path.display(), e),
+        )),
+    }
+}
+
+/// Backups of the files of the given directories, oldest first.
+pub fn list_backups(dir_paths: &[&Path]) -> Vec<Backup> {
+    let mut backups = Vec::new();
+
+    for dir_path in dir_paths {
+        let backup_dir_path = dir_path.join(BACKUP_DIR_NAME);
+        for entry in fs::read_dir(&backup_dir_path)
+            .into_iter()
+            .flatten()
+            .map_while(Result::ok)
+        {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.ends_with(LOCK_FILE_SUFFIX) {
+                continue;
+            }
+            if let Some((id, file_name)) = name.split_once('.') {
+                backups.push(Backup {
+                    id: id.to_string(),
+                    file_path: dir_path.join(file_name),
+                    backup_path: entry.path(),
+                });
+            }
+        }
+    }
+    backups.sort_by(|a, b| (&a.id, &a.file_path).cmp(&(&b.id, &b.file_path)));
+    backups.dedup();
+
+    backups
+}
+
+fn write_temp_file(temp_path: &Path, file_path: &Path, content: &str) -> io::Result<()> {
+    let mut temp_file = create_private_file(temp_path)?;
+    temp_file.write_all(content.as_bytes())?;
+    temp_file.sync_all()?;
+
+    if let Ok(metadata) = fs::metadata(file_path) {
+        fs::set_permissions(temp_path, metadata.permissions())?;
+        keep_owner(temp_path, &metadata);
+    }
+
+    Ok(())
+}
+
+/// Hold an exclusive advisory lock on `lock_path` until the returned file is dropped.
+fn lock(lock_path: &Path) -> io::Result<File> {
+    let lock_file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path)?;
+
+    for _ in 0..LOCK_ATTEMPTS {
+        match lock_file.try_lock() {
+            Ok(()) => return Ok(lock_file),
+            Err(TryLockError::WouldBlock) => thread::sleep(LOCK_RETRY_DELAY),
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::WouldBlock,
+        format!("{} is held by another process", lock_path.display()),
+    ))
+}
+
+/// Delete all but the `BACKUPS_KEPT` latest backups of `file_name`.
+fn prune_backups(backup_dir_path: &Path, file_name: &str) {
+    let dir_path = parent_of(backup_dir_path);
+    let backups = list_backups(&[dir_path])
+        .into_iter()
+        .filter(|backup| backup.file_path == dir_path.join(file_name))
+        .collect::<Vec<Backup>>();
+
+    for backup in backups.iter().rev().skip(BACKUPS_KEPT) {
+        let _ = fs::remove_file(&backup.backup_path);
+    }
+}
+
+fn parent_of(file_path: &Path) -> &Path {
+    match file_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+fn file_name_of(file_path: &Path) -> io::Result<&str> {
+    file_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a file path", file_path.display()),
+            )
+        })
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir_path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir_path)
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir_path: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir_path)
+}
+
+#[cfg(unix)]
+fn create_private_file(file_path: &Path) -> io::Result<File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(file_path)
+}
+
+#[cfg(not(unix))]
+fn create_private_file(file_path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(file_path)
+}
+
+/// Give the file the owner of `metadata` when allowed to. Only root may give a file away,
+/// anyone else writing a file they do not own makes it theirs, as an editor would.
+#[cfg(unix)]
+fn keep_owner(file_path: &Path, metadata: &fs::Metadata) {
+    use std::os::unix::fs::{chown, MetadataExt};
+
+    let _ = chown(file_path, Some(metadata.uid()), Some(metadata.gid()));
+}
+
+#[cfg(not(unix))]
+fn keep_owner(_file_path: &Path, _metadata: &fs::Metadata) {}
+
+/// Make the rename itself durable.
+#[cfg(unix)]
+fn sync_dir(dir_path: &Path) -> io::Result<()> {
+    File::open(dir_path)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::test_dir;
+
+    #[test]
+    fn write_file_safely_should_back_up_previous_content() {
+        let dir_path = test_dir("safe-write");
+        let file_path = dir_path.join("credentials");
+
+        let first = write_file_safely(&file_path, "[a]\n", None, "20210101T100000000Z").unwrap();
+        let second =
+            write_file_safely(&file_path, "[b]\n", Some("[a]\n"), "20210101T110000000Z").unwrap();
+
+        assert_eq!(first, None);
+        let second = second.unwrap();
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "[b]\n");
+        assert_eq!(fs::read_to_string(&second.backup_path).unwrap(), "[a]\n");
+        assert_eq!(list_backups(&[&dir_path]), vec![second]);
+
+        fs::remove_dir_all(&dir_path).unwrap();
+    }
+
+    #[test]
+    fn write_file_safely_should_refuse_file_changed_since_read() {
+        let dir_path = test_dir("safe-write");
+        let file_path = dir_path.join("config");
+        fs::write(&file_path, "[written by the cli]\n").unwrap();
+
+        let result = write_file_safely(&file_path, "[b]\n", Some("[a]\n"), "20210101T100000000Z");
+
+        assert!(result.is_err());
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "[written by the cli]\n"
+        );
+        assert!(list_backups(&[&dir_path]).is_empty());
+
+        fs::remove_dir_all(&dir_path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_file_safely_should_keep_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir_path = test_dir("safe-write");
+        let file_path = dir_path.join("config");
+        fs::write(&file_path, "[a]\n").unwrap();
+        fs::set_permissions(&file_path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        write_file_safely(&file_path, "[b]\n", None, "20210101T100000000Z").unwrap();
+
+        let mode = fs::metadata(&file_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+
+        fs::remove_dir_all(&dir_path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_file_safely_should_replace_the_file_a_symlink_points_to() {
+        use std::os::unix::fs::symlink;
+
+        let dir_path = test_dir("safe-write");
+        let target_dir_path = dir_path.join("dotfiles");
+        fs::create_dir(&target_dir_path).unwrap();
+        let target_path = target_dir_path.join("config");
+        fs::write(&target_path, "[a]\n").unwrap();
+        let link_path = dir_path.join("config");
+        symlink(&target_path, &link_path).unwrap();
+
+        let backup = write_file_safely(&link_path, "[b]\n", Some("[a]\n"), "20210101T100000000Z")
+            .unwrap()
+            .unwrap();
+
+        assert!(fs::symlink_metadata(&link_path)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&target_path).unwrap(), "[b]\n");
+        assert_eq!(backup.file_path, fs::canonicalize(&target_path).unwrap());
+        assert_eq!(fs::read_to_string(&backup.backup_path).unwrap(), "[a]\n");
+        assert!(!dir_path.join(BACKUP_DIR_NAME).exists());
+
+        fs::remove_dir_all(&dir_path).unwrap();
+    }
+
+    #[test]
+    fn write_file_safely_should_keep_latest_backups_only() {
+        let dir_path = test_dir("safe-write");
+        let file_path = dir_path.join("config");
+
+        for write in 0..BACKUPS_KEPT + 3 {
+            let id = format!("20210101T1000{:02}000Z", write);
+            write_file_safely(&file_path, &write.to_string(), None, &id).unwrap();
+        }
+
+        let backups = list_backups(&[&dir_path]);
+        assert_eq!(backups.len(), BACKUPS_KEPT);
+        assert_eq!(backups[0].id, "20210101T100003000Z");
+
+        fs::remove_dir_all(&dir_path).unwrap();
+    }
+}
@@ -0,0 +1,338 @@
+use std::cmp::Reverse;
+use std::io;
+
+use console::{style, Key, Term};
+use dialoguer::{theme::ColorfulTheme, Select};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+
+const MIN_PAGE_SIZE: usize = 5;
+
+/// One ranked match, `positions` are the char indexes of the item matching the query.
+struct Match {
+    index: usize,
+    score: i64,
+    positions: Vec<usize>,
+}
+
+/// Title line rendered above `items[start]` while nothing is typed.
+pub struct Section {
+    pub start: usize,
+    pub title: String,
+}
+
+/// Interactive list which fuzzy-filters `items` while typing.
+///
+/// # Return value
+///
+/// `None` - If the user leaves the list with Esc, otherwise the index of the chosen item
+pub fn pick(prompt: &str, items: &[String], default: usize) -> io::Result<Option<usize>> {
+    Finder::new(prompt, items).default(default).interact()
+}
+
+/// Builder of the list behind `pick`.
+pub struct Finder<'a> {
+    prompt: &'a str,
+    items: &'a [String],
+    boosts: &'a [i64],
+    sections: &'a [Section],
+    alerts: &'a [bool],
+    default: usize,
+}
+
+impl<'a> Finder<'a> {
+    pub fn new(prompt: &'a str, items: &'a [String]) -> Finder<'a> {
+        Finder {
+            prompt,
+            items,
+            boosts: &[],
+            sections: &[],
+            alerts: &[],
+            default: 0,
+        }
+    }
+
+    /// `boosts[i]` is added to the match score of `items[i]` so that e.g. pinned or
+    /// recently used items rank first while searching. Missing boosts count as 0.
+    pub fn boosts(mut self, boosts: &'a [i64]) -> Finder<'a> {
+        self.boosts = boosts;
+        self
+    }
+
+    /// Split the unfiltered list under titles.
+    pub fn sections(mut self, sections: &'a [Section]) -> Finder<'a> {
+        self.sections = sections;
+        self
+    }
+
+    /// Render `items[i]` in red when `alerts[i]` is set.
+    pub fn alerts(mut self, alerts: &'a [bool]) -> Finder<'a> {
+        self.alerts = alerts;
+        self
+    }
+
+    /// Index of the item under the cursor when the list opens.
+    pub fn default(mut self, default: usize) -> Finder<'a> {
+        self.default = default;
+        self
+    }
+
+    /// Falls back to a plain `Select` when the terminal is not interactive.
+    ///
+    /// # Return value
+    ///
+    /// `None` - If the user leaves the list with Esc, otherwise the index of the chosen item
+    pub fn interact(&self) -> io::Result<Option<usize>> {
+        let term = Term::stderr();
+        if !term.is_term() {
+            let items = (0..self.items.len())
+                .map(|index| self.styled(index, &self.items[index]))
+                .collect::<Vec<String>>();
+            return Select::with_theme(&ColorfulTheme::default())
+                .with_prompt(self.prompt)
+                .default(self.default)
+                .items(&items)
+                .paged(true)
+                .interact_opt();
+        }
+
+        term.hide_cursor()?;
+        let result = self.interact_on(&term);
+        term.show_cursor()?;
+
+        result
+    }
+
+    fn interact_on(&self, term: &Term) -> io::Result<Option<usize>> {
+        let matcher = SkimMatcherV2::default().ignore_case();
+        let page_size = (term.size().0 as usize)
+            .saturating_sub(2 + self.sections.len())
+            .max(MIN_PAGE_SIZE);
+        let mut query = String::new();
+        let mut matches = rank(&matcher, self.items, self.boosts, &query);
+        let mut cursor = matches
+            .iter()
+            .position(|m| m.index == self.default)
+            .unwrap_or(0);
+        let mut rendered_lines = 0;
+
+        loop {
+            term.clear_last_lines(rendered_lines)?;
+            rendered_lines = self.render(term, &query, &matches, cursor, page_size)?;
+
+            match term.read_key() {
+                Ok(Key::ArrowUp) | Ok(Key::BackTab) if !matches.is_empty() => {
+                    cursor = (cursor + matches.len() - 1) % matches.len();
+                }
+                Ok(Key::ArrowDown) | Ok(Key::Tab) if !matches.is_empty() => {
+                    cursor = (cursor + 1) % matches.len();
+                }
+                Ok(Key::PageUp) => cursor = cursor.saturating_sub(page_size),
+                Ok(Key::PageDown) if !matches.is_empty() => {
+                    cursor = (cursor + page_size).min(matches.len() - 1);
+                }
+                Ok(Key::Enter) if !matches.is_empty() => {
+                    let index = matches[cursor].index;
+                    let chosen = if self.is_alert(index) {
+                        style(&self.items[index]).red()
+                    } else {
+                        style(&self.items[index]).green()
+                    };
+                    term.clear_last_lines(rendered_lines)?;
+                    term.write_line(&format!(
+                        "{} {} {} {}",
+                        style("✔").green(),
+                        style(self.prompt).bold(),
+                        style("·").black().bright(),
+                        chosen
+                    ))?;
+                    return Ok(Some(index));
+                }
+                Ok(Key::Escape) => {
+                    term.clear_last_lines(rendered_lines)?;
+                    return Ok(None);
+                }
+                Ok(Key::Backspace) => {
+                    query.pop();
+                    matches = rank(&matcher, self.items, self.boosts, &query);
+                    cursor = 0;
+                }
+                Ok(Key::Char(character)) if !character.is_control() => {
+                    query.push(character);
+                    matches = rank(&matcher, self.items, self.boosts, &query);
+                    cursor = 0;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    term.clear_last_lines(rendered_lines)?;
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    fn render(
+        &self,
+        term: &Term,
+        query: &str,
+        matches: &[Match],
+        cursor: usize,
+        page_size: usize,
+    ) -> io::Result<usize> {
+        term.write_line(&format!(
+            "{} {} {} {}",
+            style("?").yellow(),
+            style(self.prompt).bold(),
+            style("›").black().bright(),
+            query
+        ))?;
+
+        if matches.is_empty() {
+            term.write_line(&format!("  {}", style("(no match)").black().bright()))?;
+            return Ok(2);
+        }
+
+        let sections = if query.is_empty() { self.sections } else { &[] };
+        let page_start = cursor / page_size * page_size;
+        let page = &matches[page_start..matches.len().min(page_start + page_size)];
+        let mut rendered_lines = page.len() + 1;
+        for (offset, item_match) in page.iter().enumerate() {
+            if let Some(section) = sections.iter().find(|s| s.start == item_match.index) {
+                term.write_line(&format!(
+                    "  {}",
+                    style(format!("── {} ──", section.title)).black().bright()
+                ))?;
+                rendered_lines += 1;
+            }
+
+            let is_active = page_start + offset == cursor;
+            let line = highlight(
+                &self.items[item_match.index],
+                &item_match.positions,
+                is_active,
+                self.is_alert(item_match.index),
+            );
+            if is_active {
+                term.write_line(&format!("{} {}", style("❯").green(), line))?;
+            } else {
+                term.write_line(&format!("  {}", line))?;
+            }
+        }
+
+        Ok(rendered_lines)
+    }
+
+    fn styled(&self, index: usize, text: &str) -> String {
+        if self.is_alert(index) {
+            style(text).red().to_string()
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn is_alert(&self, index: usize) -> bool {
+        self.alerts.get(index).copied().unwrap_or(false)
+    }
+}
+
+fn highlight(item: &str, positions: &[usize], is_active: bool, is_alert: bool) -> String {
+    item.chars()
+        .enumerate()
+        .map(|(index, character)| {
+            let character = style(character);
+            let character = if is_alert {
+                character.red()
+            } else if is_active {
+                character.cyan()
+            } else {
+                character
+            };
+            if positions.contains(&index) {
+                character.yellow().bold().to_string()
+            } else {
+                character.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Items matching `query`, best score first. Ties keep the order of `items`.
+/// An empty query keeps the order of `items` so that the menu opens the same way every time.
+fn rank(matcher: &SkimMatcherV2, items: &[String], boosts: &[i64], query: &str) -> Vec<Match> {
+    let mut matches = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| {
+            if query.is_empty() {
+                return Some(Match {
+                    index,
+                    score: 0,
+                    positions: Vec::new(),
+                });
+            }
+
+            let (score, positions) = matcher.fuzzy_indices(item, query)?;
+            Some(Match {
+                index,
+                score: score + boosts.get(index).copied().unwrap_or(0),
+                positions,
+            })
+        })
+        .collect::<Vec<Match>>();
+
+    matches.sort_by_key(|m| Reverse(m.score));
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> Vec<String> {
+        vec![
+            "eu-west-1 | Ireland".to_string(),
+            "eu-west-2 | London".to_string(),
+            "us-east-1 | N. Virginia".to_string(),
+        ]
+    }
+
+    fn ranked_indexes(boosts: &[i64], query: &str) -> Vec<usize> {
+        let matcher = SkimMatcherV2::default().ignore_case();
+
+        rank(&matcher, &items(), boosts, query)
+            .iter()
+            .map(|m| m.index)
+            .collect()
+    }
+
+    #[test]
+    fn rank_should_return_every_item_in_order_when_query_is_empty() {
+        assert_eq!(ranked_indexes(&[], ""), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn rank_should_fuzzy_match_ignoring_case() {
+        assert_eq!(ranked_indexes(&[], "EUlon"), vec![1]);
+        assert_eq!(ranked_indexes(&[], "usva"), vec![2]);
+        assert!(ranked_indexes(&[], "tokyo").is_empty());
+    }
+
+    #[test]
+    fn rank_should_put_boosted_items_first_when_searching() {
+        assert_eq!(ranked_indexes(&[0, 1000], "west"), vec![1, 0]);
+    }
+
+    #[test]
+    fn rank_should_ignore_boosts_when_query_is_empty() {
+        assert_eq!(ranked_indexes(&[0, 0, 10], ""), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn rank_should_return_positions_of_matched_characters() {
+        let matcher = SkimMatcherV2::default().ignore_case();
+
+        let matches = rank(&matcher, &items(), &[], "lon");
+
+        assert_eq!(matches[0].positions, vec![12, 13, 14]);
+    }
+}
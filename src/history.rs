@@ -0,0 +1,206 @@
+use std::env;
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+
+use crate::file::create_file_reader_for;
+use crate::file::home::get_awsp_file_path;
+
+const HISTORY_FILE_NAME: &str = "history";
+const FIELD_SEPARATOR: char = '\t';
+
+/// One committed profile/region switch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    pub profile: String,
+    pub region: String,
+    pub cwd: String,
+}
+
+impl HistoryEntry {
+    /// Entry for a switch happening now in the current directory.
+    pub fn new(profile: &str, region: &str) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: Utc::now(),
+            profile: profile.to_string(),
+            region: region.to_string(),
+            cwd: env::current_dir()
+                .map(|cwd| cwd.display().to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// `timestamp<TAB>profile<TAB>region<TAB>cwd`
+    fn to_line(&self) -> String {
+        [
+            self.timestamp.to_rfc3339(),
+            self.profile.clone(),
+            self.region.clone(),
+            self.cwd.clone(),
+        ]
+        .join(&FIELD_SEPARATOR.to_string())
+    }
+
+    fn from_line(line: &str) -> Option<HistoryEntry> {
+        let mut fields = line.splitn(4, FIELD_SEPARATOR);
+
+        Some(HistoryEntry {
+            timestamp: fields.next()?.parse::<DateTime<Utc>>().ok()?,
+            profile: fields.next()?.to_string(),
+            region: fields.next()?.to_string(),
+            cwd: fields.next().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+/// Switch history from `~/.awsp/history`, oldest first.
+pub fn load_history() -> Vec<HistoryEntry> {
+    match get_awsp_file_path(HISTORY_FILE_NAME) {
+        Ok(location) => read_history_from(location.as_path()),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn read_history_from(history_file_path: &Path) -> Vec<HistoryEntry> {
+    if !history_file_path.is_file() {
+        return Vec::new();
+    }
+
+    create_file_reader_for(history_file_path)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| HistoryEntry::from_line(&line))
+        .collect()
+}
+
+/// Append `entry` to `~/.awsp/history`, keeping the last `history_length` entries.
+pub fn record_switch(entry: &HistoryEntry, history_length: usize) -> io::Result<()> {
+    let location = get_awsp_file_path(HISTORY_FILE_NAME)
+        .map_err(|e| io::Error::new(io::ErrorKind::NotFound, e.message))?;
+
+    append_history_to(location.as_path(), entry, history_length)
+}
+
+pub fn append_history_to(
+    history_file_path: &Path,
+    entry: &HistoryEntry,
+    history_length: usize,
+) -> io::Result<()> {
+    let mut history = read_history_from(history_file_path);
+    history.push(entry.clone());
+    let skip = history.len().saturating_sub(history_length);
+
+    if let Some(parent) = history_file_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content = history[skip..]
+        .iter()
+        .map(|entry| entry.to_line() + "\n")
+        .collect::<String>();
+    fs::write(history_file_path, content)
+}
+
+/// Distinct profiles of `history`, most recently used first.
+pub fn recent_profiles(history: &[HistoryEntry]) -> Vec<String> {
+    history
+        .iter()
+        .rev()
+        .fold(Vec::new(), |mut profiles, entry| {
+            if !profiles.contains(&entry.profile) {
+                profiles.push(entry.profile.clone());
+            }
+            profiles
+        })
+}
+
+/// The most recent entry switching to another profile/region pair than the current one,
+/// what `awsp -` goes back to.
+pub fn previous_entry<'a>(
+    history: &'a [HistoryEntry],
+    current_profile: &str,
+    current_region: &str,
+) -> Option<&'a HistoryEntry> {
+    history
+        .iter()
+        .rev()
+        .find(|entry| entry.profile != current_profile || entry.region != current_region)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_dir::test_dir;
+
+    fn entry(profile: &str, region: &str) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: "2021-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+            profile: profile.to_string(),
+            region: region.to_string(),
+            cwd: "/tmp".to_string(),
+        }
+    }
+
+    #[test]
+    fn read_history_from_should_parse_every_valid_line() {
+        let result = read_history_from(Path::new("tests/sample-data/awsp_history"));
+
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0].profile, "foo");
+        assert_eq!(result[0].region, "us-east-1");
+        assert_eq!(result[0].cwd, "/home/user/project");
+        assert_eq!(result[2].profile, "bar");
+    }
+
+    #[test]
+    fn append_history_to_should_keep_last_history_length_entries() {
+        let dir_path = test_dir("history");
+        let history_file_path = dir_path.join("history");
+
+        append_history_to(&history_file_path, &entry("a", "us-east-1"), 2).unwrap();
+        append_history_to(&history_file_path, &entry("b", "us-east-1"), 2).unwrap();
+        append_history_to(&history_file_path, &entry("c", "us-east-1"), 2).unwrap();
+
+        let result = read_history_from(&history_file_path);
+        assert_eq!(
+            result,
+            vec![entry("b", "us-east-1"), entry("c", "us-east-1")]
+        );
+
+        fs::remove_dir_all(&dir_path).unwrap();
+    }
+
+    #[test]
+    fn recent_profiles_should_list_distinct_profiles_most_recent_first() {
+        let history = vec![
+            entry("a", "us-east-1"),
+            entry("b", "us-east-1"),
+            entry("a", "eu-west-1"),
+        ];
+
+        assert_eq!(recent_profiles(&history), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn previous_entry_should_skip_entries_equal_to_current_pair() {
+        let history = vec![
+            entry("a", "us-east-1"),
+            entry("b", "eu-west-1"),
+            entry("b", "eu-west-1"),
+        ];
+
+        assert_eq!(
+            previous_entry(&history, "b", "eu-west-1"),
+            Some(&entry("a", "us-east-1"))
+        );
+        assert_eq!(
+            previous_entry(&history, "a", "us-east-1"),
+            Some(&entry("b", "eu-west-1"))
+        );
+        assert_eq!(previous_entry(&history[..1], "a", "us-east-1"), None);
+    }
+}
@@ -1 +1,16 @@
+pub mod audit;
+pub mod bundle;
+pub mod doctor;
+pub mod environment;
 pub mod file;
+pub mod history;
+pub mod pin;
+pub mod profile;
+pub mod region;
+pub mod schema;
+pub mod session;
+pub mod settings;
+pub mod sts;
+
+#[cfg(test)]
+mod test_dir;
@@ -4,11 +4,19 @@ extern crate colour;
 use cmdline::Opt;
 
 pub mod cmdline;
+mod command;
+mod finder;
 mod selector;
 
 fn main() {
     let opt = Opt::parse();
-    selector::run(&opt)
+    if opt.strict {
+        command::doctor::enforce_schema();
+    }
+    match &opt.command {
+        Some(command) => command::run(command, &opt),
+        None => selector::run(&opt),
+    }
     // dbg!(opt);
     // TODO Error Handler
     // if let Err(e) = selector::run(&opt) {
@@ -0,0 +1,118 @@
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+
+use crate::file::create_file_reader_for;
+use crate::file::home::get_awsp_file_path;
+
+const PINS_FILE_NAME: &str = "pins";
+
+/// Pinned profiles from `~/.awsp/pins`, in pin order.
+pub fn load_pins() -> Vec<String> {
+    match get_awsp_file_path(PINS_FILE_NAME) {
+        Ok(location) => read_pins_from(location.as_path()),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn read_pins_from(pins_file_path: &Path) -> Vec<String> {
+    if !pins_file_path.is_file() {
+        return Vec::new();
+    }
+
+    create_file_reader_for(pins_file_path)
+        .lines()
+        .map_while(Result::ok)
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Pin `profile` in `~/.awsp/pins`.
+///
+/// # Return value
+///
+/// `false` - If the profile was already pinned
+pub fn pin(profile: &str) -> io::Result<bool> {
+    add_pin_to(pins_file_path()?.as_path(), profile)
+}
+
+/// Unpin `profile` in `~/.awsp/pins`.
+///
+/// # Return value
+///
+/// `false` - If the profile was not pinned
+pub fn unpin(profile: &str) -> io::Result<bool> {
+    remove_pin_from(pins_file_path()?.as_path(), profile)
+}
+
+pub fn add_pin_to(pins_file_path: &Path, profile: &str) -> io::Result<bool> {
+    let mut pins = read_pins_from(pins_file_path);
+    if pins.iter().any(|pinned| pinned == profile) {
+        return Ok(false);
+    }
+
+    pins.push(profile.to_string());
+    write_pins_to(pins_file_path, &pins)?;
+    Ok(true)
+}
+
+pub fn remove_pin_from(pins_file_path: &Path, profile: &str) -> io::Result<bool> {
+    let mut pins = read_pins_from(pins_file_path);
+    let size = pins.len();
+    pins.retain(|pinned| pinned != profile);
+    if pins.len() == size {
+        return Ok(false);
+    }
+
+    write_pins_to(pins_file_path, &pins)?;
+    Ok(true)
+}
+
+fn pins_file_path() -> io::Result<PathBuf> {
+    get_awsp_file_path(PINS_FILE_NAME)
+        .map_err(|e| io::Error::new(io::ErrorKind::NotFound, e.message))
+}
+
+fn write_pins_to(pins_file_path: &Path, pins: &[String]) -> io::Result<()> {
+    if let Some(parent) = pins_file_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(
+        pins_file_path,
+        pins.iter()
+            .map(|pin| pin.clone() + "\n")
+            .collect::<String>(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_dir::test_dir;
+
+    #[test]
+    fn add_pin_to_and_remove_pin_from_should_keep_pin_order() {
+        let dir_path = test_dir("pins");
+        let pins_file_path = dir_path.join("pins");
+
+        assert!(add_pin_to(&pins_file_path, "prod").unwrap());
+        assert!(add_pin_to(&pins_file_path, "dev").unwrap());
+        assert!(!add_pin_to(&pins_file_path, "prod").unwrap());
+        assert_eq!(read_pins_from(&pins_file_path), vec!["prod", "dev"]);
+
+        assert!(remove_pin_from(&pins_file_path, "prod").unwrap());
+        assert!(!remove_pin_from(&pins_file_path, "prod").unwrap());
+        assert_eq!(read_pins_from(&pins_file_path), vec!["dev"]);
+
+        fs::remove_dir_all(&dir_path).unwrap();
+    }
+
+    #[test]
+    fn read_pins_from_should_return_empty_when_given_path_is_not_exist() {
+        assert!(read_pins_from(Path::new("some/nonsense/path")).is_empty());
+    }
+}
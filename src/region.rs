@@ -0,0 +1,412 @@
+pub mod partition;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::BufRead;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+
+use crate::file::create_file_reader_for;
+use crate::file::helper::line::{get_section_name_from, IniLine, IniReader, Sections};
+use crate::file::home::get_awsp_file_path;
+
+const EMBEDDED_REGIONS: &str = include_str!("region/regions.ini");
+const USER_REGIONS_FILE_NAME: &str = "regions";
+const DEFAULT_PARTITION: &str = "aws";
+const DEFAULT_GEOGRAPHY: &str = "Other";
+const AWSP_REGIONS_KEY: &str = "awsp_regions";
+
+/// One AWS region as described by the region catalog.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    pub code: String,
+    pub name: String,
+    pub partition: String,
+    pub geography: String,
+    pub opt_in: bool,
+    pub timezone: Option<String>,
+}
+
+impl Region {
+    fn new(code: String) -> Region {
+        Region {
+            name: code.clone(),
+            code,
+            partition: DEFAULT_PARTITION.to_string(),
+            geography: DEFAULT_GEOGRAPHY.to_string(),
+            opt_in: false,
+            timezone: None,
+        }
+    }
+
+    /// Local `HH:MM` of the region at `now`, if the catalog knows its time zone.
+    pub fn local_time_at(&self, now: DateTime<Utc>) -> Option<String> {
+        let timezone = self.timezone.as_ref()?.parse::<Tz>().ok()?;
+
+        Some(now.with_timezone(&timezone).format("%H:%M").to_string())
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "name" => self.name = value.to_string(),
+            "partition" => self.partition = value.to_string(),
+            "geography" => self.geography = value.to_string(),
+            "opt_in" => self.opt_in = value.eq_ignore_ascii_case("true"),
+            "timezone" => self.timezone = Some(value.to_string()),
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for Region {
+    /// `us-east-2 | Ohio`, code padded with the formatter width.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = f.width().unwrap_or(0);
+        write!(f, "{:width$} | {}", self.code, self.name, width = width)?;
+        if self.opt_in {
+            write!(f, " (opt-in)")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Ordered list of regions, the single source of truth for region display and value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionCatalog {
+    regions: Vec<Region>,
+}
+
+impl RegionCatalog {
+    /// Catalog shipped inside the binary.
+    pub fn embedded() -> RegionCatalog {
+        RegionCatalog {
+            regions: parse_regions_from(EMBEDDED_REGIONS.as_bytes()),
+        }
+    }
+
+    /// Embedded catalog overridden by `~/.awsp/regions` when that file exists.
+    pub fn load() -> RegionCatalog {
+        let mut catalog = RegionCatalog::embedded();
+
+        if let Ok(location) = get_awsp_file_path(USER_REGIONS_FILE_NAME) {
+            catalog.override_with_file(location.as_path());
+        }
+
+        catalog
+    }
+
+    /// Apply the keys of `regions_file_path` onto the regions with the same code, keeping the
+    /// fields the file does not set, and append the regions the catalog does not know.
+    pub fn override_with_file(&mut self, regions_file_path: &Path) {
+        if !regions_file_path.is_file() {
+            return;
+        }
+
+        for (code, entries) in parse_region_entries_from(create_file_reader_for(regions_file_path))
+        {
+            let region = match self.regions.iter().position(|r| r.code == code) {
+                Some(index) => &mut self.regions[index],
+                None => {
+                    self.regions.push(Region::new(code));
+                    self.regions.last_mut().unwrap()
+                }
+            };
+            for (key, value) in entries {
+                region.set(&key, &value);
+            }
+        }
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    pub fn get(&self, code: &str) -> Option<&Region> {
+        self.regions.iter().find(|region| region.code == code)
+    }
+
+    /// Catalog restricted to the regions of `partition`.
+    pub fn for_partition(&self, partition: &str) -> RegionCatalog {
+        RegionCatalog {
+            regions: self
+                .regions
+                .iter()
+                .filter(|region| region.partition == partition)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Catalog restricted to `codes`, in the given order.
+    /// Codes unknown to the catalog are kept with default attributes.
+    pub fn restrict_to(&self, codes: &[String]) -> RegionCatalog {
+        RegionCatalog {
+            regions: codes
+                .iter()
+                .map(|code| {
+                    self.get(code)
+                        .cloned()
+                        .unwrap_or_else(|| Region::new(code.clone()))
+                })
+                .collect(),
+        }
+    }
+
+    /// Distinct geographies in catalog order.
+    pub fn geographies(&self) -> Vec<&str> {
+        let mut geographies: Vec<&str> = Vec::new();
+
+        for region in &self.regions {
+            if !geographies.contains(&region.geography.as_str()) {
+                geographies.push(&region.geography);
+            }
+        }
+
+        geographies
+    }
+
+    /// Index of the region with `code` in catalog order.
+    pub fn position(&self, code: &str) -> Option<usize> {
+        self.regions.iter().position(|region| region.code == code)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// Menu lines with region codes aligned, in catalog order.
+    pub fn display_list(&self) -> Vec<String> {
+        let width = self
+            .regions
+            .iter()
+            .map(|region| region.code.len())
+            .max()
+            .unwrap_or(0);
+
+        self.regions
+            .iter()
+            .map(|region| format!("{:width$}", region, width = width))
+            .collect()
+    }
+}
+
+/// Regions a profile is limited to by the awsp-only `awsp_regions = a, b` key.
+///
+/// # Return value
+///
+/// `None` - If the profile does not limit its regions
+pub fn allowed_regions_for(profile_config: &HashMap<String, String>) -> Option<Vec<String>> {
+    let regions = profile_config
+        .get(AWSP_REGIONS_KEY)?
+        .split(',')
+        .map(str::trim)
+        .filter(|region| !region.is_empty())
+        .map(String::from)
+        .collect::<Vec<String>>();
+
+    if regions.is_empty() {
+        None
+    } else {
+        Some(regions)
+    }
+}
+
+fn parse_regions_from<R: BufRead>(reader: R) -> Vec<Region> {
+    parse_region_entries_from(reader)
+        .into_iter()
+        .map(|(code, entries)| {
+            let mut region = Region::new(code);
+            for (key, value) in entries {
+                region.set(&key, &value);
+            }
+            region
+        })
+        .collect()
+}
+
+/// Code and `key = value` pairs of each region section, in file order.
+fn parse_region_entries_from<R: BufRead>(reader: R) -> Vec<(String, Vec<(String, String)>)> {
+    let mut regions: Vec<(String, Vec<(String, String)>)> = Vec::new();
+    let mut ini_reader = IniReader::new();
+
+    for line in reader.lines().map_while(Result::ok) {
+        match ini_reader.read(&line) {
+            IniLine::Header(header) => {
+                if let Some(code) = get_section_name_from(&header, Sections::Titles) {
+                    regions.push((code, Vec::new()));
+                }
+            }
+            IniLine::Entry { key, value } => {
+                if let Some((_, entries)) = regions.last_mut() {
+                    entries.push((key, value));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    regions
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn embedded_should_parse_every_region_with_unique_code() {
+        let catalog = RegionCatalog::embedded();
+
+        let codes = catalog
+            .regions()
+            .iter()
+            .map(|region| region.code.as_str())
+            .collect::<HashSet<&str>>();
+
+        assert_eq!(codes.len(), catalog.regions().len());
+        assert!(codes.contains("cn-northwest-1"));
+        assert!(!codes.contains("cn-nortwest-1"));
+        assert!(codes.contains("il-central-1"));
+        assert!(codes.contains("us-gov-west-1"));
+    }
+
+    #[test]
+    fn embedded_should_describe_region_with_all_fields() {
+        let catalog = RegionCatalog::embedded();
+
+        let region = catalog.get("af-south-1").expect("No af-south-1 in catalog");
+
+        assert_eq!(region.name, "Cape Town");
+        assert_eq!(region.partition, "aws");
+        assert_eq!(region.geography, "Africa");
+        assert!(region.opt_in);
+        assert_eq!(catalog.get("cn-north-1").unwrap().partition, "aws-cn");
+    }
+
+    #[test]
+    fn override_with_file_should_override_existing_and_append_new_regions() {
+        let mut catalog = RegionCatalog::embedded();
+        let size = catalog.regions().len();
+
+        catalog.override_with_file(Path::new("tests/sample-data/regions_override"));
+
+        assert_eq!(catalog.regions().len(), size + 1);
+        assert_eq!(catalog.get("us-east-1").unwrap().name, "Home");
+        let beijing = catalog.get("cn-north-1").unwrap();
+        assert_eq!(beijing.name, "Beijing (Sinnet)");
+        assert_eq!(beijing.partition, "aws-cn");
+        assert_eq!(beijing.geography, "China");
+        assert_eq!(beijing.timezone, Some("Asia/Shanghai".to_string()));
+        assert!(catalog.for_partition("aws").get("cn-north-1").is_none());
+        let local = catalog.regions().last().unwrap();
+        assert_eq!(local.code, "local-1");
+        assert_eq!(local.geography, DEFAULT_GEOGRAPHY);
+    }
+
+    #[test]
+    fn for_partition_should_keep_only_regions_of_given_partition() {
+        let catalog = RegionCatalog::embedded().for_partition("aws-cn");
+
+        let codes = catalog
+            .regions()
+            .iter()
+            .map(|region| region.code.as_str())
+            .collect::<Vec<&str>>();
+
+        assert_eq!(codes, vec!["cn-north-1", "cn-northwest-1"]);
+    }
+
+    #[test]
+    fn position_should_return_index_of_given_region_code() {
+        let catalog = RegionCatalog::embedded().for_partition("aws-cn");
+
+        assert_eq!(catalog.position("cn-northwest-1"), Some(1));
+        assert_eq!(catalog.position("us-east-1"), None);
+    }
+
+    #[test]
+    fn restrict_to_should_keep_given_order_and_unknown_regions() {
+        let catalog = RegionCatalog::embedded()
+            .restrict_to(&["eu-central-1".to_string(), "local-1".to_string()]);
+
+        assert_eq!(catalog.regions().len(), 2);
+        assert_eq!(catalog.regions()[0].name, "Frankfurt");
+        assert_eq!(catalog.regions()[1].code, "local-1");
+    }
+
+    #[test]
+    fn allowed_regions_for_should_split_comma_separated_regions() {
+        let mut config = HashMap::new();
+        config.insert(
+            AWSP_REGIONS_KEY.to_string(),
+            "eu-west-1, eu-central-1,".to_string(),
+        );
+
+        assert_eq!(
+            allowed_regions_for(&config),
+            Some(vec!["eu-west-1".to_string(), "eu-central-1".to_string()])
+        );
+    }
+
+    #[test]
+    fn allowed_regions_for_should_return_none_when_profile_has_no_limit() {
+        let mut config = HashMap::new();
+        config.insert("region".to_string(), "eu-west-1".to_string());
+
+        assert_eq!(allowed_regions_for(&config), None);
+
+        config.insert(AWSP_REGIONS_KEY.to_string(), " ".to_string());
+
+        assert_eq!(allowed_regions_for(&config), None);
+    }
+
+    #[test]
+    fn geographies_should_list_each_geography_once_in_catalog_order() {
+        let catalog = RegionCatalog::embedded();
+
+        let geographies = catalog.geographies();
+
+        assert_eq!(geographies[0], "North America");
+        assert_eq!(geographies.iter().filter(|g| **g == "Europe").count(), 1);
+        assert!(geographies.contains(&"AWS GovCloud"));
+    }
+
+    #[test]
+    fn local_time_at_should_convert_to_region_time_zone() {
+        let catalog = RegionCatalog::embedded();
+        let now = "2021-01-01T00:30:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        assert_eq!(
+            catalog.get("ap-northeast-1").unwrap().local_time_at(now),
+            Some("09:30".to_string())
+        );
+        assert_eq!(
+            catalog.get("us-east-1").unwrap().local_time_at(now),
+            Some("19:30".to_string())
+        );
+        assert_eq!(Region::new("local-1".into()).local_time_at(now), None);
+    }
+
+    #[test]
+    fn display_list_should_align_region_codes() {
+        let catalog = RegionCatalog {
+            regions: parse_regions_from(
+                "[us-east-2]\nname = Ohio\n[ap-northeast-3]\nname = Osaka\nopt_in = true\n"
+                    .as_bytes(),
+            ),
+        };
+
+        assert_eq!(
+            catalog.display_list(),
+            vec![
+                "us-east-2      | Ohio".to_string(),
+                "ap-northeast-3 | Osaka (opt-in)".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use crate::region::RegionCatalog;
+
+const DEFAULT_PARTITION: &str = "aws";
+const PARTITION_REGION_PREFIXES: &[(&str, &str)] = &[
+    ("cn-", "aws-cn"),
+    ("us-gov-", "aws-us-gov"),
+    ("us-isob-", "aws-iso-b"),
+    ("us-iso-", "aws-iso"),
+];
+
+/// Infer the partition a profile lives in, from the first of `role_arn`, `sso_region`
+/// and `region` that is configured.
+///
+/// # Return value
+///
+/// `None` - If the profile configures none of them
+pub fn infer_partition(
+    profile_config: &HashMap<String, String>,
+    catalog: &RegionCatalog,
+) -> Option<String> {
+    profile_config
+        .get("role_arn")
+        .and_then(|arn| partition_from_arn(arn))
+        .or_else(|| {
+            ["sso_region", "region"].iter().find_map(|key| {
+                profile_config
+                    .get(*key)
+                    .map(|region| partition_of_region(region, catalog))
+            })
+        })
+}
+
+/// Partition of an ARN, `arn:partition:service:region:account-id:resource`.
+pub fn partition_from_arn(arn: &str) -> Option<String> {
+    let mut fields = arn.split(':');
+
+    match (fields.next(), fields.next()) {
+        (Some("arn"), Some(partition)) if !partition.is_empty() => Some(partition.to_string()),
+        _ => None,
+    }
+}
+
+/// Partition of a region from the catalog, falling back to the region code prefix.
+pub fn partition_of_region(region_code: &str, catalog: &RegionCatalog) -> String {
+    match catalog.get(region_code) {
+        Some(region) => region.partition.clone(),
+        None => PARTITION_REGION_PREFIXES
+            .iter()
+            .find(|(prefix, _)| region_code.starts_with(prefix))
+            .map_or(DEFAULT_PARTITION, |(_, partition)| partition)
+            .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile_config(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn infer_partition_should_prefer_role_arn_partition() {
+        let config = profile_config(&[
+            ("role_arn", "arn:aws-us-gov:iam::123456789012:role/admin"),
+            ("region", "us-east-1"),
+        ]);
+
+        let result = infer_partition(&config, &RegionCatalog::embedded());
+
+        assert_eq!(result, Some("aws-us-gov".to_string()));
+    }
+
+    #[test]
+    fn infer_partition_should_use_sso_region_before_region() {
+        let config = profile_config(&[("sso_region", "cn-north-1"), ("region", "us-east-1")]);
+
+        let result = infer_partition(&config, &RegionCatalog::embedded());
+
+        assert_eq!(result, Some("aws-cn".to_string()));
+    }
+
+    #[test]
+    fn infer_partition_should_return_none_when_profile_has_no_hint() {
+        let config = profile_config(&[("output", "json")]);
+
+        assert_eq!(infer_partition(&config, &RegionCatalog::embedded()), None);
+    }
+
+    #[test]
+    fn partition_of_region_should_fall_back_to_region_prefix_when_region_is_not_in_catalog() {
+        let catalog = RegionCatalog::embedded();
+
+        assert_eq!(partition_of_region("cn-south-9", &catalog), "aws-cn");
+        assert_eq!(partition_of_region("us-iso-east-1", &catalog), "aws-iso");
+        assert_eq!(partition_of_region("xx-new-1", &catalog), "aws");
+    }
+
+    #[test]
+    fn partition_from_arn_should_return_none_when_given_value_is_not_arn() {
+        assert_eq!(partition_from_arn("not-an-arn"), None);
+    }
+}
@@ -0,0 +1,462 @@
+use regex::Regex;
+
+use crate::doctor::{FileKind, Problem};
+use crate::file::document::BYTE_ORDER_MARK;
+use crate::file::helper::line::{get_section_name_from, IniLine, IniReader, Sections};
+
+const SSO_SESSION_SECTION_PREFIX: &str = "sso-session ";
+const SERVICES_SECTION_PREFIX: &str = "services ";
+/// config file sections that are not profiles and hold free-form keys
+pub(crate) const FREE_FORM_SECTIONS: &[&str] = &["plugins", "preview"];
+
+/// What a value must look like.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueSpec {
+    Any,
+    Arn,
+    Region,
+    Integer,
+    Boolean,
+    AccountId,
+    Url,
+    OneOf(&'static [&'static str]),
+    /// byte size like `8MB`
+    Size,
+    /// byte rate like `50MB/s`
+    Rate,
+    /// `key =` followed by indented keys of the given list
+    Nested(&'static [KeySpec]),
+    /// `key =` followed by indented keys of any name
+    NestedAny,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeySpec {
+    pub name: &'static str,
+    pub value: ValueSpec,
+}
+
+const fn key(name: &'static str, value: ValueSpec) -> KeySpec {
+    KeySpec { name, value }
+}
+
+const BOOLEAN: ValueSpec = ValueSpec::Boolean;
+const INTEGER: ValueSpec = ValueSpec::Integer;
+const ANY: ValueSpec = ValueSpec::Any;
+
+const S3_KEYS: &[KeySpec] = &[
+    key(
+        "addressing_style",
+        ValueSpec::OneOf(&["auto", "path", "virtual"]),
+    ),
+    key("io_chunksize", ValueSpec::Size),
+    key("max_bandwidth", ValueSpec::Rate),
+    key("max_concurrent_requests", INTEGER),
+    key("max_queue_size", INTEGER),
+    key("multipart_chunksize", ValueSpec::Size),
+    key("multipart_threshold", ValueSpec::Size),
+    key("payload_signing_enabled", BOOLEAN),
+    key(
+        "preferred_transfer_client",
+        ValueSpec::OneOf(&["auto", "classic", "crt"]),
+    ),
+    key("target_bandwidth", ValueSpec::Rate),
+    key(
+        "us_east_1_regional_endpoint",
+        ValueSpec::OneOf(&["legacy", "regional"]),
+    ),
+    key("use_accelerate_endpoint", BOOLEAN),
+    key("use_arn_region", BOOLEAN),
+    key("use_dualstack_endpoint", BOOLEAN),
+];
+
+const SERVICE_KEYS: &[KeySpec] = &[key("endpoint_url", ValueSpec::Url)];
+
+/// Keys of a profile read by the AWS CLI and SDKs, and by awsp.
+pub const PROFILE_KEYS: &[KeySpec] = &[
+    key(
+        "account_id_endpoint_mode",
+        ValueSpec::OneOf(&["preferred", "disabled", "required"]),
+    ),
+    key("api_versions", ValueSpec::NestedAny),
+    key("aws_access_key_id", ANY),
+    key("aws_account_id", ValueSpec::AccountId),
+    key("aws_secret_access_key", ANY),
+    key("aws_security_token", ANY),
+    key("aws_session_token", ANY),
+    key("auth_scheme_preference", ANY),
+    key("awsp_env", ANY),
+    key("awsp_regions", ANY),
+    key("ca_bundle", ANY),
+    key(
+        "cli_auto_prompt",
+        ValueSpec::OneOf(&["on", "off", "on-partial"]),
+    ),
+    key(
+        "cli_binary_format",
+        ValueSpec::OneOf(&["base64", "raw-in-base64-out"]),
+    ),
+    key("cli_follow_urlparam", BOOLEAN),
+    key("cli_history", ValueSpec::OneOf(&["enabled", "disabled"])),
+    key("cli_pager", ANY),
+    key(
+        "cli_timestamp_format",
+        ValueSpec::OneOf(&["iso8601", "wire", "none"]),
+    ),
+    key("credential_process", ANY),
+    key("csm_client_id", ANY),
+    key("csm_enabled", BOOLEAN),
+    key("csm_host", ANY),
+    key("csm_port", INTEGER),
+    key(
+        "credential_source",
+        ValueSpec::OneOf(&["Environment", "Ec2InstanceMetadata", "EcsContainer"]),
+    ),
+    key(
+        "defaults_mode",
+        ValueSpec::OneOf(&[
+            "standard",
+            "in-region",
+            "cross-region",
+            "mobile",
+            "auto",
+            "legacy",
+        ]),
+    ),
+    key("disable_request_compression", BOOLEAN),
+    key("duration_seconds", INTEGER),
+    key("ec2_metadata_service_endpoint", ValueSpec::Url),
+    key(
+        "ec2_metadata_service_endpoint_mode",
+        ValueSpec::OneOf(&["IPv4", "IPv6"]),
+    ),
+    key("ec2_metadata_v1_disabled", BOOLEAN),
+    key(
+        "endpoint_discovery_enabled",
+        ValueSpec::OneOf(&["true", "false", "auto", "True", "False", "Auto"]),
+    ),
+    key("endpoint_url", ValueSpec::Url),
+    key("external_id", ANY),
+    key("ignore_configure_endpoint_urls", BOOLEAN),
+    key("imds_use_ipv6", BOOLEAN),
+    key("max_attempts", INTEGER),
+    key("metadata_service_num_attempts", INTEGER),
+    key("metadata_service_timeout", INTEGER),
+    key("mfa_serial", ANY),
+    key(
+        "output",
+        ValueSpec::OneOf(&["json", "text", "table", "yaml", "yaml-stream"]),
+    ),
+    key("parameter_validation", BOOLEAN),
+    key("region", ValueSpec::Region),
+    key(
+        "request_checksum_calculation",
+        ValueSpec::OneOf(&["when_supported", "when_required"]),
+    ),
+    key("request_min_compression_size_bytes", INTEGER),
+    key(
+        "response_checksum_validation",
+        ValueSpec::OneOf(&["when_supported", "when_required"]),
+    ),
+    key(
+        "retry_mode",
+        ValueSpec::OneOf(&["standard", "adaptive", "legacy"]),
+    ),
+    key("role_arn", ValueSpec::Arn),
+    key("role_session_name", ANY),
+    key("s3", ValueSpec::Nested(S3_KEYS)),
+    key("s3_disable_express_session_auth", BOOLEAN),
+    key("s3_use_arn_region", BOOLEAN),
+    key("sdk_ua_app_id", ANY),
+    key("services", ANY),
+    key("sigv4a_signing_region_set", ANY),
+    key("source_profile", ANY),
+    key("sso_account_id", ValueSpec::AccountId),
+    key("sso_region", ValueSpec::Region),
+    key("sso_registration_scopes", ANY),
+    key("sso_role_name", ANY),
+    key("sso_session", ANY),
+    key("sso_start_url", ValueSpec::Url),
+    key(
+        "sts_regional_endpoints",
+        ValueSpec::OneOf(&["legacy", "regional"]),
+    ),
+    key("tcp_keepalive", BOOLEAN),
+    key("use_dualstack_endpoint", BOOLEAN),
+    key("use_fips_endpoint", BOOLEAN),
+    key("web_identity_token_file", ANY),
+    // written next to temporary keys by some credential helpers
+    key("aws_expiration", ANY),
+    key("expiration", ANY),
+    key("x_security_token_expires", ANY),
+];
+
+pub const SSO_SESSION_KEYS: &[KeySpec] = &[
+    key("sso_region", ValueSpec::Region),
+    key("sso_registration_scopes", ANY),
+    key("sso_start_url", ValueSpec::Url),
+];
+
+/// Keys a section may hold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SectionSchema {
+    Keys(&'static [KeySpec]),
+    /// `[services x]`: any service name, each followed by an indented block of `SERVICE_KEYS`
+    Services,
+    /// not checked
+    FreeForm,
+}
+
+/// Unknown keys and invalid values of a config or credentials file, with their 1-based line.
+pub fn check_schema(content: &str, kind: FileKind) -> Vec<(usize, Problem)> {
+    let mut problems = Vec::new();
+    let mut section = SectionSchema::FreeForm;
+    // keys allowed in the indented block under `key =`, `None` for any key
+    let mut nested_keys: Option<Option<&[KeySpec]>> = None;
+
+    let mut reader = IniReader::new();
+
+    for (index, line) in content
+        .trim_start_matches(BYTE_ORDER_MARK)
+        .lines()
+        .enumerate()
+    {
+        let (name, value, allowed) = match (reader.read(line), nested_keys, section) {
+            (IniLine::Header(header), _, _) => {
+                section = schema_of_section(&header, kind);
+                nested_keys = None;
+                continue;
+            }
+            // botocore splits nested lines on `=` only
+            (IniLine::Continuation(text), Some(Some(keys)), _) => match text.trim().split_once('=')
+            {
+                Some((name, value)) => (name.trim().to_string(), value.trim().to_string(), keys),
+                None => continue,
+            },
+            (IniLine::Entry { key, value }, _, SectionSchema::Keys(keys)) => {
+                nested_keys = None;
+                (key, value, keys)
+            }
+            (IniLine::Entry { .. }, _, SectionSchema::Services) => {
+                nested_keys = Some(Some(SERVICE_KEYS));
+                continue;
+            }
+            _ => continue,
+        };
+        let (name, value) = (name.as_str(), value.as_str());
+
+        match allowed.iter().find(|spec| spec.name == name) {
+            Some(spec) => match spec.value {
+                ValueSpec::Nested(keys) if value.is_empty() => nested_keys = Some(Some(keys)),
+                ValueSpec::NestedAny if value.is_empty() => nested_keys = Some(None),
+                spec_value => {
+                    if let Some(expected) = invalid_value(spec_value, value) {
+                        problems.push((
+                            index + 1,
+                            Problem::InvalidValue {
+                                key: name.to_string(),
+                                value: value.to_string(),
+                                expected,
+                            },
+                        ));
+                    }
+                }
+            },
+            None => problems.push((
+                index + 1,
+                Problem::UnknownKey {
+                    key: name.to_string(),
+                    suggestion: closest_key(name, allowed),
+                },
+            )),
+        }
+    }
+
+    problems
+}
+
+fn schema_of_section(header: &str, kind: FileKind) -> SectionSchema {
+    let title = header.trim_start_matches('[').trim_end_matches(']').trim();
+
+    if kind == FileKind::Credentials {
+        SectionSchema::Keys(PROFILE_KEYS)
+    } else if title.starts_with(SSO_SESSION_SECTION_PREFIX) {
+        SectionSchema::Keys(SSO_SESSION_KEYS)
+    } else if title.starts_with(SERVICES_SECTION_PREFIX) {
+        SectionSchema::Services
+    } else if FREE_FORM_SECTIONS.contains(&title)
+        || get_section_name_from(header, Sections::Config).is_none()
+    {
+        SectionSchema::FreeForm
+    } else {
+        SectionSchema::Keys(PROFILE_KEYS)
+    }
+}
+
+/// What `value` should have looked like, `None` when it is fine.
+fn invalid_value(spec: ValueSpec, value: &str) -> Option<String> {
+    let matches = |pattern: &str| Regex::new(pattern).unwrap().is_match(value);
+
+    let (valid, expected) = match spec {
+        ValueSpec::Any | ValueSpec::Nested(_) | ValueSpec::NestedAny => (true, String::new()),
+        ValueSpec::Arn => (
+            matches(r"^arn:aws[a-z-]*:[a-z0-9-]+:[a-z0-9-]*:(\d{12})?:\S+$"),
+            "an ARN".to_string(),
+        ),
+        ValueSpec::Region => (
+            matches(r"^[a-z]{2}(-[a-z]+)+-\d+$"),
+            "a region code like us-east-1".to_string(),
+        ),
+        ValueSpec::Integer => (value.parse::<u64>().is_ok(), "an integer".to_string()),
+        ValueSpec::Boolean => (
+            value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false"),
+            "true or false".to_string(),
+        ),
+        ValueSpec::AccountId => (matches(r"^\d{12}$"), "12 digits".to_string()),
+        ValueSpec::Url => (matches(r"^https?://\S+$"), "a URL".to_string()),
+        ValueSpec::OneOf(values) => (
+            values.contains(&value),
+            format!("one of {}", values.join(", ")),
+        ),
+        ValueSpec::Size => (
+            matches(r"^\d+\s*([KMG]i?B)?$"),
+            "a size like 8MB".to_string(),
+        ),
+        ValueSpec::Rate => (
+            matches(r"^\d+\s*([KMG]i?B|B)/s$"),
+            "a rate like 50MB/s".to_string(),
+        ),
+    };
+
+    if valid {
+        None
+    } else {
+        Some(expected)
+    }
+}
+
+/// Closest known key, when it is close enough to be a typo.
+fn closest_key(name: &str, keys: &[KeySpec]) -> Option<String> {
+    let max_distance = 2.max(name.len() / 4);
+
+    keys.iter()
+        .map(|spec| (edit_distance(name, spec.name), spec.name))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, key)| key.to_string())
+}
+
+/// Levenshtein distance, counting an adjacent swap as one edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<char>>();
+    let b = b.chars().collect::<Vec<char>>();
+    let mut distances = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn check_schema_should_suggest_known_keys_for_typos() {
+        let content = fs::read_to_string("tests/sample-data/typo_config").unwrap();
+
+        assert_eq!(
+            check_schema(&content, FileKind::Config),
+            vec![
+                (
+                    2,
+                    Problem::UnknownKey {
+                        key: "regoin".to_string(),
+                        suggestion: Some("region".to_string())
+                    }
+                ),
+                (
+                    6,
+                    Problem::UnknownKey {
+                        key: "souce_profile".to_string(),
+                        suggestion: Some("source_profile".to_string())
+                    }
+                ),
+                (
+                    7,
+                    Problem::InvalidValue {
+                        key: "retry_mode".to_string(),
+                        value: "fast".to_string(),
+                        expected: "one of standard, adaptive, legacy".to_string()
+                    }
+                ),
+                (
+                    10,
+                    Problem::InvalidValue {
+                        key: "max_concurrent_requests".to_string(),
+                        value: "ten".to_string(),
+                        expected: "an integer".to_string()
+                    }
+                ),
+                (
+                    11,
+                    Problem::UnknownKey {
+                        key: "endpoint_url".to_string(),
+                        suggestion: None
+                    }
+                ),
+                (
+                    18,
+                    Problem::UnknownKey {
+                        key: "sso_role_name".to_string(),
+                        suggestion: None
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_schema_should_accept_valid_config() {
+        let content = fs::read_to_string("tests/sample-data/listed_profiles_config").unwrap();
+
+        assert_eq!(check_schema(&content, FileKind::Config), vec![]);
+    }
+
+    #[test]
+    fn check_schema_should_accept_keys_botocore_reads() {
+        let content = "[default]\n\
+                       endpoint_discovery_enabled = auto\n\
+                       sdk_ua_app_id = team-tools\n\
+                       auth_scheme_preference = sigv4a,sigv4\n\
+                       imds_use_ipv6 = true\n";
+
+        assert_eq!(check_schema(content, FileKind::Config), vec![]);
+    }
+
+    #[test]
+    fn edit_distance_should_count_swaps_as_one_edit() {
+        assert_eq!(edit_distance("regoin", "region"), 1);
+        assert_eq!(edit_distance("souce_profile", "source_profile"), 1);
+        assert_eq!(edit_distance("output", "output"), 0);
+    }
+}
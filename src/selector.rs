@@ -1,4 +1,5 @@
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
 
     use super::*;
@@ -33,6 +34,101 @@ mod tests {
         assert!(result.iter().any(|&key| key == "key_3"));
     }
 
+    #[test]
+    fn profile_label_should_join_account_role_and_description() {
+        let mut config = HashMap::new();
+        config.insert(
+            String::from("role_arn"),
+            String::from("arn:aws:iam::123456789012:role/admin"),
+        );
+        config.insert(String::from("awsp_description"), String::from("Payments"));
+
+        let result = profile_label(
+            "prod",
+            Some(String::from("123456789012")),
+            Some(&config),
+            Some(Origin::User),
+            6,
+        );
+
+        assert_eq!(result, "prod    123456789012  admin  Payments");
+    }
+
+    #[test]
+    fn profile_label_should_return_name_when_profile_has_nothing_to_show() {
+        assert_eq!(profile_label("dev", None, None, None, 6), "dev");
+    }
+
+    #[test]
+    fn profile_label_should_tell_profiles_of_shared_files() {
+        assert_eq!(
+            profile_label("team", None, None, Some(Origin::Shared), 6),
+            "team    (shared)"
+        );
+    }
+
+    fn ordered(order: ProfileOrder) -> Vec<String> {
+        let profiles = [String::from("b"), String::from("c"), String::from("a")];
+        let file_order = vec![String::from("c"), String::from("a")];
+        let recent = vec![String::from("b")];
+        let mut profile_list = profiles.iter().collect::<Vec<&String>>();
+
+        order_profiles(&mut profile_list, order, &file_order, &recent);
+
+        profile_list.into_iter().cloned().collect()
+    }
+
+    #[test]
+    fn order_profiles_should_sort_alphabetically() {
+        assert_eq!(ordered(ProfileOrder::Alphabetical), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn order_profiles_should_follow_file_order_then_alphabetical() {
+        assert_eq!(ordered(ProfileOrder::File), vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn order_profiles_should_put_recent_profiles_first() {
+        assert_eq!(ordered(ProfileOrder::Recent), vec!["b", "a", "c"]);
+    }
+
+    #[test]
+    fn recency_boost_should_rank_most_recent_profile_highest() {
+        let recent = vec![String::from("a"), String::from("b")];
+
+        assert!(recency_boost(&recent, "a") > recency_boost(&recent, "b"));
+        assert_eq!(recency_boost(&recent, "c"), 0);
+    }
+
+    #[test]
+    fn group_profiles_should_put_pinned_then_recent_profiles_first() {
+        let profiles = ["a", "b", "c", "d"].map(String::from);
+        let mut profile_list = profiles.iter().collect::<Vec<&String>>();
+        let pins = vec![String::from("c")];
+        let recent = vec![String::from("c"), String::from("d"), String::from("x")];
+
+        let sections = group_profiles(&mut profile_list, &pins, &recent);
+
+        assert_eq!(profile_list, vec!["c", "d", "a", "b"]);
+        assert_eq!(
+            sections
+                .iter()
+                .map(|s| (s.start, s.title.as_str()))
+                .collect::<Vec<(usize, &str)>>(),
+            vec![(0, "pinned"), (1, "recent"), (2, "all")]
+        );
+    }
+
+    #[test]
+    fn group_profiles_should_keep_order_when_nothing_is_pinned_or_recent() {
+        let profiles = ["b", "a"].map(String::from);
+        let mut profile_list = profiles.iter().collect::<Vec<&String>>();
+
+        assert!(group_profiles(&mut profile_list, &[], &[]).is_empty());
+        assert_eq!(profile_list, vec!["b", "a"]);
+    }
+
     // Flaky test
     // #[test]
     // fn parse_default_env_no_value() {
@@ -51,62 +147,45 @@ mod tests {
 }
 
 use crate::cmdline::Opt;
+use crate::command::whoami;
+use crate::finder::{self, Finder, Section};
 
-use awsp::file::config::{create_profile_config_map_from, get_aws_config_file_path};
+use awsp::audit::{self, AuditEntry, SwitchMode};
+use awsp::environment::{environment_of, is_protected};
+use awsp::file::cache::{cached_expiry_of, get_aws_dir_path};
+use awsp::file::config::{create_profile_config_map_from, create_profile_list_from};
+use awsp::file::config::{
+    ProfileConfigMap, AWSP_USER_CONFIG_FILE_ENV_VAR_NAME, AWS_CONFIG_FILE_ENV_VAR_NAME,
+};
+use awsp::file::credential::{
+    create_profile_account_id_map_from, get_aws_credentials_file_path, parse_credentials_file,
+};
+use awsp::file::helper::line::Sections;
+use awsp::file::layers::{get_merged_config_file_path, write_merged_config};
+use awsp::file::layers::{LayeredConfig, Origin};
+use awsp::history::{load_history, previous_entry, recent_profiles, record_switch, HistoryEntry};
+use awsp::pin::load_pins;
+use awsp::region::partition::infer_partition;
+use awsp::region::{allowed_regions_for, RegionCatalog};
+use awsp::session::{record_session, SessionState};
+use awsp::settings::{ProfileOrder, RegionPicker, Settings};
+use awsp::sts::expected_account_id_from;
 
-use dialoguer::{theme::ColorfulTheme, Select};
+use chrono::{DateTime, Utc};
+use dialoguer::{theme::ColorfulTheme, Input, Select};
 use std::env;
 use std::path::PathBuf;
 use std::process::Command;
 use std::{collections::HashMap, process};
 use sysinfo::{get_current_pid, ProcessExt, Signal, System, SystemExt};
 
-const REGIONS_DISPLAY: &[&str] = &[
-    "us-east-2      | Ohio",
-    "us-east-1      | N. Virginia",
-    "us-west-1      | N. California",
-    "us-west-2      | Oregon",
-    "ap-south-1     | Mumbai",
-    "ap-northeast-3 | Osaka-Local",
-    "ap-northeast-2 | Seoul",
-    "ap-northeast-1 | Tokyo",
-    "ap-southeast-1 | Singapore",
-    "ap-southeast-2 | Sydney",
-    "ca-central-1   | Central",
-    "cn-north-1     | Beijing",
-    "cn-nortwest-1  | Ningxia",
-    "eu-central-1   | Frankfurt",
-    "eu-west-1      | Ireland",
-    "eu-west-2      | London",
-    "eu-west-3      | Paris",
-    "eu-north-1     | Stockholm",
-    "sa-east-1      | São Paulo",
-];
-
-const REGIONS: &[&str] = &[
-    "us-east-2",
-    "us-east-1",
-    "us-west-1",
-    "us-west-2",
-    "ap-south-1",
-    "ap-northeast-3",
-    "ap-northeast-2",
-    "ap-northeast-1",
-    "ap-southeast-1",
-    "ap-southeast-2",
-    "ca-central-1",
-    "cn-north-1",
-    "cn-nortwest-1",
-    "eu-central-1",
-    "eu-west-1",
-    "eu-west-2",
-    "eu-west-3",
-    "eu-north-1",
-    "sa-east-1",
-];
-
 const AWS_DEFAULT_PROFILE: &str = "AWS_PROFILE";
 const AWS_DEFAULT_REGION: &str = "AWS_DEFAULT_REGION";
+const REGION_ENV_VAR_NAMES: &[&str] = &[AWS_DEFAULT_REGION, "AWS_REGION"];
+const AWSP_DESCRIPTION_KEY: &str = "awsp_description";
+const RECENCY_BOOST_STEP: i64 = 10;
+const PIN_BOOST: i64 = 50;
+const RECENT_PROFILES_IN_MENU: usize = 5;
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 // TODO Error Handler
@@ -116,47 +195,596 @@ pub fn run(opt: &Opt) {
         print!("\nawsp: ");
         green_ln!("{}\n", VERSION);
         process::exit(1);
-    } else if opt.region {
-        region_menu();
-    } else {
-        profile_menu();
-        region_menu();
     }
 
-    display_selected();
+    let mode = if opt.previous.is_some() {
+        previous_menu();
+        SwitchMode::Previous
+    } else if opt.region.is_some() {
+        region_menu(opt);
+        SwitchMode::Region
+    } else {
+        profile_menu(opt.account.as_deref(), opt.lenient_sections);
+        region_menu(opt);
+        SwitchMode::Menu
+    };
 
-    exec_process();
+    commit_switch(opt, mode);
 
     // TODO Error Handler
     // Ok(())
 }
 
-fn display_selected() {
+/// Confirm a protected profile, display the selected profile/region,
+/// record it to the switch history and audit log and start a shell with it.
+pub fn commit_switch(opt: &Opt, mode: SwitchMode) {
+    let settings = Settings::load();
+    let profile = default_env(AWS_DEFAULT_PROFILE);
+    let config = load_config(opt.lenient_sections);
+    let config_file = config.to_map();
+    let environment = environment_of(&profile, config_file.get(&profile), &settings);
+    let protected = is_protected(environment.as_deref(), &settings);
+    let reason = if protected {
+        confirm_protected(&profile, environment.as_deref(), settings.require_reason)
+    } else {
+        None
+    };
+
+    display_selected(environment.as_deref(), protected);
+
+    if opt.verify {
+        whoami::verify(
+            &default_env(AWS_DEFAULT_PROFILE),
+            opt.sts_endpoint.as_deref(),
+            opt.lenient_sections,
+        );
+    }
+
+    let entry = HistoryEntry::new(
+        &default_env(AWS_DEFAULT_PROFILE),
+        &default_env(AWS_DEFAULT_REGION),
+    );
+    if let Err(e) = record_switch(&entry, settings.history_length) {
+        e_yellow_ln!("Failed to record switch history: {}", e);
+    }
+
+    let credentials_sections = Sections::of_credentials_file(opt.lenient_sections);
+    let account = account_id_of(&profile, &config_file, credentials_sections);
+    if !profile.is_empty() {
+        let state = SessionState {
+            profile: profile.clone(),
+            account: account.clone(),
+            expiry: cached_expiry_for(&profile, &config_file, credentials_sections),
+        };
+        if let Err(e) = record_session(&state) {
+            e_yellow_ln!("Failed to record session: {}", e);
+        }
+    }
+
+    let audit_entry = AuditEntry {
+        account,
+        environment,
+        reason,
+        ..AuditEntry::new(&entry.profile, &entry.region, mode)
+    };
+    if let Err(e) = audit::record(&audit_entry, settings.audit_rotation) {
+        e_yellow_ln!("Failed to write audit log: {}", e);
+    }
+
+    select_config_file(&config, &settings);
+    exec_process();
+}
+
+/// The config merged from the user's file and the shared ones, see `LayeredConfig`.
+fn load_config(lenient: bool) -> LayeredConfig {
+    LayeredConfig::load_default(Sections::of_config_file(lenient)).unwrap_or_else(|e| {
+        red_ln!("\n{}\n", e.message);
+        process::exit(1);
+    })
+}
+
+/// Point the AWS CLI at the merged config when `export_merged_config` asks for it and profiles
+/// come from shared files it does not read itself, otherwise back at the user's own file.
+fn select_config_file(config: &LayeredConfig, settings: &Settings) {
+    let user_file_path = config.user_file_path().to_path_buf();
+
+    if config.exports_merged_config(settings) {
+        let written = get_merged_config_file_path()
+            .map_err(|e| e.message)
+            .and_then(|location| {
+                write_merged_config(config, &location)
+                    .map(|_| location)
+                    .map_err(|e| e.to_string())
+            });
+        match written {
+            Ok(location) => {
+                env::set_var(AWSP_USER_CONFIG_FILE_ENV_VAR_NAME, user_file_path);
+                env::set_var(AWS_CONFIG_FILE_ENV_VAR_NAME, location);
+            }
+            Err(e) => {
+                e_yellow_ln!("Failed to write the merged config: {}", e);
+            }
+        }
+    } else if env::var_os(AWSP_USER_CONFIG_FILE_ENV_VAR_NAME).is_some() {
+        env::set_var(AWS_CONFIG_FILE_ENV_VAR_NAME, user_file_path);
+        env::remove_var(AWSP_USER_CONFIG_FILE_ENV_VAR_NAME);
+    }
+}
+
+fn display_selected(environment: Option<&str>, protected: bool) {
+    let environment = environment
+        .map(|environment| format!(" [{}]", environment))
+        .unwrap_or_default();
+
     // clear screen charactor
     print!("{esc}c", esc = 27 as char);
-    green!("\n ->");
+    if protected {
+        red!("\n ->");
+    } else {
+        green!("\n ->");
+    }
     print!("  Profile: ");
-    green!("{}", default_env("AWS_PROFILE"));
+    if protected {
+        red!("{}{}", default_env("AWS_PROFILE"), environment);
+    } else {
+        green!("{}{}", default_env("AWS_PROFILE"), environment);
+    }
     print!(" | Region: ");
     green_ln!("{} \n", default_env("AWS_DEFAULT_REGION"));
 }
 
-fn profile_menu() {
-    let location = get_aws_config_file_path().unwrap();
-    let config_file = create_profile_config_map_from(location.as_path()).unwrap();
-    let profile_list = to_key_list(&config_file);
-    let profile_list = profile_list.as_slice();
+/// Account of `profile` as far as it is known without calling AWS:
+/// the role or SSO account first, then the account of its static access key.
+fn account_id_of(
+    profile: &str,
+    config_file: &ProfileConfigMap,
+    credentials_sections: Sections,
+) -> Option<String> {
+    config_file
+        .get(profile)
+        .and_then(expected_account_id_from)
+        .or_else(|| {
+            let credentials = get_aws_credentials_file_path()
+                .and_then(|location| {
+                    parse_credentials_file(location.as_path(), credentials_sections)
+                })
+                .unwrap_or_default();
+            create_profile_account_id_map_from(&credentials).remove(profile)
+        })
+}
+
+/// Expiry of the cached temporary credentials of `profile`, for the prompt countdown.
+fn cached_expiry_for(
+    profile: &str,
+    config_file: &ProfileConfigMap,
+    credentials_sections: Sections,
+) -> Option<DateTime<Utc>> {
+    let mut profile_config = get_aws_credentials_file_path()
+        .ok()
+        .and_then(|location| {
+            create_profile_config_map_from(location.as_path(), credentials_sections)
+        })
+        .and_then(|mut credentials| credentials.remove(profile))
+        .unwrap_or_default();
+    if let Some(config) = config_file.get(profile) {
+        profile_config.extend(config.clone());
+    }
+
+    cached_expiry_of(&profile_config, config_file, &get_aws_dir_path()?)
+}
+
+/// Make the user type the profile name, then ask for a reason when `require_reason` is set.
+/// Exit when the name does not match.
+///
+/// # Return value
+///
+/// The reason given, to be written to the audit log
+fn confirm_protected(
+    profile: &str,
+    environment: Option<&str>,
+    require_reason: bool,
+) -> Option<String> {
+    red_ln!(
+        "\n {} is a protected {} profile",
+        profile,
+        environment.unwrap_or_default()
+    );
+    let typed = Input::<String>::with_theme(&ColorfulTheme::default())
+        .with_prompt("Type the profile name to confirm")
+        .allow_empty(true)
+        .interact_text()
+        .unwrap_or_default();
+    if typed.trim() != profile {
+        red_ln!("\nProfile name does not match, nothing switched\n");
+        process::exit(1);
+    }
+
+    if !require_reason {
+        return None;
+    }
+    match Input::<String>::with_theme(&ColorfulTheme::default())
+        .with_prompt("Reason")
+        .interact_text()
+    {
+        Ok(reason) if !reason.trim().is_empty() => Some(reason.trim().to_string()),
+        _ => {
+            red_ln!("\nA reason is required, nothing switched\n");
+            process::exit(1);
+        }
+    }
+}
+
+/// Go back to the profile/region used before the current one, like `cd -`.
+fn previous_menu() {
+    let history = load_history();
+    let previous = previous_entry(
+        &history,
+        &default_env(AWS_DEFAULT_PROFILE),
+        &default_env(AWS_DEFAULT_REGION),
+    );
+
+    match previous {
+        Some(entry) => {
+            select_profile(&entry.profile);
+            select_region(&entry.region);
+        }
+        None => {
+            red_ln!("\nNo previous profile in switch history\n");
+            process::exit(1);
+        }
+    }
+}
+
+fn profile_menu(account: Option<&str>, lenient: bool) {
+    let credentials_sections = Sections::of_credentials_file(lenient);
+    let config = load_config(lenient);
+    let config_file = config.to_map();
+    let credentials = get_aws_credentials_file_path()
+        .and_then(|location| parse_credentials_file(location.as_path(), credentials_sections))
+        .unwrap_or_default();
+    let key_account_ids = create_profile_account_id_map_from(&credentials);
+    let recent_profiles = recent_profiles(&load_history());
+    let pins = load_pins();
+    let settings = Settings::load();
+
+    let mut file_order = config.profile_names();
+    if let Ok(credentials_location) = get_aws_credentials_file_path() {
+        file_order.extend(
+            create_profile_list_from(credentials_location.as_path(), credentials_sections)
+                .unwrap_or_default(),
+        );
+    }
+    // the config file also has `[sso-session x]` and `[services x]` sections
+    let mut profile_list = to_key_list(&config_file);
+    profile_list.retain(|profile| file_order.contains(profile));
+    for profile in credentials.keys() {
+        if !config_file.contains_key(profile) {
+            profile_list.push(profile);
+        }
+    }
+    order_profiles(
+        &mut profile_list,
+        settings.profile_order,
+        &file_order,
+        &recent_profiles,
+    );
+    if let Some(account) = account {
+        profile_list.retain(|profile| {
+            key_account_ids.get(*profile).map(String::as_str) == Some(account)
+                || config_file.get(*profile).and_then(expected_account_id_from)
+                    == Some(account.to_string())
+        });
+    }
+    if profile_list.is_empty() {
+        red_ln!(
+            "\nNo profile found{}\n",
+            account
+                .map(|a| format!(" for account {}", a))
+                .unwrap_or_default()
+        );
+        process::exit(1);
+    }
+    let sections = group_profiles(&mut profile_list, &pins, &recent_profiles);
+
+    let name_width = profile_list.iter().map(|p| p.len()).max().unwrap_or(0);
+    let display_list = profile_list
+        .iter()
+        .map(|profile| {
+            let account_id = key_account_ids
+                .get(*profile)
+                .cloned()
+                .or_else(|| config_file.get(*profile).and_then(expected_account_id_from));
+            let origin = config.origin_of(profile).map(|layer| layer.origin);
+            profile_label(
+                profile,
+                account_id,
+                config_file.get(*profile),
+                origin,
+                name_width,
+            )
+        })
+        .collect::<Vec<String>>();
+    let boosts = profile_list
+        .iter()
+        .map(|profile| {
+            let pin_boost = if pins.contains(profile) { PIN_BOOST } else { 0 };
+            pin_boost + recency_boost(&recent_profiles, profile)
+        })
+        .collect::<Vec<i64>>();
+    let alerts = profile_list
+        .iter()
+        .map(|profile| {
+            let environment = environment_of(profile, config_file.get(*profile), &settings);
+            is_protected(environment.as_deref(), &settings)
+        })
+        .collect::<Vec<bool>>();
     let default_profile = default_env("AWS_PROFILE");
+    let cursor = profile_list
+        .iter()
+        .position(|profile| **profile == default_profile)
+        .unwrap_or(0);
     let display_prompt = format!("profile (current: {} )", default_profile);
-    let selection = display(display_prompt, profile_list, 0);
+    let selection = match Finder::new(&display_prompt, &display_list)
+        .boosts(&boosts)
+        .sections(&sections)
+        .alerts(&alerts)
+        .default(cursor)
+        .interact()
+    {
+        Ok(Some(selection)) => selection,
+        _ => process::exit(1),
+    };
     select_profile(profile_list[selection]);
 }
 
-fn region_menu() {
+/// Sort profiles by the configured order, ties alphabetical.
+fn order_profiles(
+    profile_list: &mut [&String],
+    order: ProfileOrder,
+    file_order: &[String],
+    recent_profiles: &[String],
+) {
+    let rank_in = |list: &[String], profile: &String| {
+        list.iter()
+            .position(|listed| listed == profile)
+            .unwrap_or(usize::MAX)
+    };
+
+    profile_list.sort();
+    match order {
+        ProfileOrder::Alphabetical => {}
+        ProfileOrder::File => profile_list.sort_by_key(|profile| rank_in(file_order, profile)),
+        ProfileOrder::Recent => {
+            profile_list.sort_by_key(|profile| rank_in(recent_profiles, profile))
+        }
+    }
+}
+
+/// Move pinned profiles first in pin order, then the few most recent ones,
+/// the rest keeps its order.
+///
+/// # Return value
+///
+/// Menu sections, empty if nothing is pinned or recent
+fn group_profiles(
+    profile_list: &mut Vec<&String>,
+    pins: &[String],
+    recent_profiles: &[String],
+) -> Vec<Section> {
+    fn take<'a>(profile_list: &mut Vec<&'a String>, profile: &String) -> Option<&'a String> {
+        let position = profile_list.iter().position(|listed| *listed == profile)?;
+        Some(profile_list.remove(position))
+    }
+
+    let pinned = pins
+        .iter()
+        .filter_map(|pin| take(profile_list, pin))
+        .collect::<Vec<&String>>();
+    let recent = recent_profiles
+        .iter()
+        .filter_map(|recent| take(profile_list, recent))
+        .take(RECENT_PROFILES_IN_MENU)
+        .collect::<Vec<&String>>();
+    if pinned.is_empty() && recent.is_empty() {
+        return Vec::new();
+    }
+
+    let mut sections = Vec::new();
+    let mut start = 0;
+    for (title, size) in [
+        ("pinned", pinned.len()),
+        ("recent", recent.len()),
+        ("all", profile_list.len()),
+    ] {
+        if size > 0 {
+            sections.push(Section {
+                start,
+                title: title.to_string(),
+            });
+        }
+        start += size;
+    }
+    let mut rest = std::mem::replace(profile_list, pinned);
+    profile_list.extend(recent);
+    profile_list.append(&mut rest);
+
+    sections
+}
+
+/// `name  account  role  description  (origin)`, the text the profile finder searches through.
+/// The origin is left out for profiles of the user's own files.
+fn profile_label(
+    profile: &str,
+    account_id: Option<String>,
+    profile_config: Option<&HashMap<String, String>>,
+    origin: Option<Origin>,
+    name_width: usize,
+) -> String {
+    let role_name = profile_config.and_then(|profile_config| {
+        profile_config
+            .get("role_arn")
+            .and_then(|arn| arn.rsplit('/').next())
+            .or_else(|| profile_config.get("sso_role_name").map(String::as_str))
+    });
+    let description = profile_config
+        .and_then(|profile_config| profile_config.get(AWSP_DESCRIPTION_KEY))
+        .map(String::as_str);
+
+    let origin = origin
+        .filter(|origin| *origin != Origin::User)
+        .map(|origin| format!("({})", origin));
+
+    let mut label = format!("{:width$}", profile, width = name_width);
+    for column in [
+        account_id.as_deref(),
+        role_name,
+        description,
+        origin.as_deref(),
+    ]
+    .iter()
+    .flatten()
+    {
+        label.push_str("  ");
+        label.push_str(column);
+    }
+
+    label.trim_end().to_string()
+}
+
+/// Score bonus of `profile` in the finder, higher the more recently it was selected.
+fn recency_boost(recent_profiles: &[String], profile: &str) -> i64 {
+    recent_profiles
+        .iter()
+        .position(|recent| recent == profile)
+        .map_or(0, |rank| {
+            (recent_profiles.len() - rank) as i64 * RECENCY_BOOST_STEP
+        })
+}
+
+fn region_menu(opt: &Opt) {
+    let config_file = load_config(opt.lenient_sections).to_map();
+    let profile_config = config_file.get(&default_env(AWS_DEFAULT_PROFILE));
+    let profile_region = profile_config.and_then(|profile_config| profile_config.get("region"));
+    let allowed_regions = profile_config.and_then(allowed_regions_for);
+
+    if let Some(Some(region)) = &opt.region {
+        if let Some(allowed_regions) = &allowed_regions {
+            if !allowed_regions.contains(region) {
+                red_ln!(
+                    "\nRegion {} is not allowed for profile {} (awsp_regions = {})\n",
+                    region,
+                    default_env(AWS_DEFAULT_PROFILE),
+                    allowed_regions.join(", ")
+                );
+                process::exit(1);
+            }
+        }
+        select_region(region);
+        return;
+    }
+
+    if let (true, Some(region)) = (opt.keep_region, profile_region) {
+        select_region(region);
+        return;
+    }
+
     let default_region = default_env("AWS_DEFAULT_REGION");
-    let display_prompt = format!("region (current: {} )", default_region);
-    let selection = display(display_prompt, REGIONS_DISPLAY, 0);
-    select_region(REGIONS[selection]);
+    let mut display_prompt = format!("region (current: {} )", default_region);
+    let mut catalog = RegionCatalog::load();
+
+    if let Some(allowed_regions) = &allowed_regions {
+        catalog = catalog.restrict_to(allowed_regions);
+    } else if !opt.any_partition {
+        let partition =
+            profile_config.and_then(|profile_config| infer_partition(profile_config, &catalog));
+
+        if let Some(partition) = partition {
+            let partition_catalog = catalog.for_partition(&partition);
+            if !partition_catalog.is_empty() {
+                display_prompt = format!(
+                    "region (current: {}, partition: {} )",
+                    default_region, partition
+                );
+                catalog = partition_catalog;
+            }
+        }
+    }
+
+    let mut display_list = catalog.display_list();
+    let cursor = match profile_region.and_then(|region| catalog.position(region)) {
+        Some(index) => {
+            display_list[index].push_str(" (profile default)");
+            index
+        }
+        None => REGION_ENV_VAR_NAMES
+            .iter()
+            .find_map(|name| catalog.position(&default_env(name)))
+            .unwrap_or(0),
+    };
+    let selection = match Settings::load().region_picker {
+        RegionPicker::Flat => display(display_prompt, &display_list, cursor),
+        RegionPicker::Grouped => {
+            grouped_region_display(display_prompt, &catalog, &display_list, cursor)
+        }
+    };
+    select_region(&catalog.regions()[selection].code);
+}
+
+/// Two level region menu, geography first, then the regions of that geography with their local time.
+/// Esc on the region level goes back to the geographies.
+fn grouped_region_display(
+    display_prompt: String,
+    catalog: &RegionCatalog,
+    display_list: &[String],
+    default: usize,
+) -> usize {
+    let geographies = catalog.geographies();
+    let regions = catalog.regions();
+    let now = Utc::now();
+    let mut geography_selection = geographies
+        .iter()
+        .position(|geography| *geography == regions[default].geography)
+        .unwrap_or(0);
+
+    loop {
+        if geographies.len() > 1 {
+            let geography_list = geographies
+                .iter()
+                .map(|geography| {
+                    let count = regions.iter().filter(|r| r.geography == *geography).count();
+                    format!("{} ({})", geography, count)
+                })
+                .collect::<Vec<String>>();
+            geography_selection =
+                match finder::pick(&display_prompt, &geography_list, geography_selection) {
+                    Ok(Some(selection)) => selection,
+                    _ => process::exit(1),
+                };
+        }
+
+        let geography = geographies[geography_selection];
+        let region_indexes = (0..regions.len())
+            .filter(|&index| regions[index].geography == geography)
+            .collect::<Vec<usize>>();
+        let region_list = region_indexes
+            .iter()
+            .map(|&index| match regions[index].local_time_at(now) {
+                Some(local_time) => format!("{}  {}", display_list[index], local_time),
+                None => display_list[index].clone(),
+            })
+            .collect::<Vec<String>>();
+        let region_default = region_indexes
+            .iter()
+            .position(|&index| index == default)
+            .unwrap_or(0);
+
+        let region_prompt = format!("{} › {}", display_prompt, geography);
+        match finder::pick(&region_prompt, &region_list, region_default) {
+            Ok(Some(selection)) => return region_indexes[selection],
+            Ok(None) if geographies.len() > 1 => continue,
+            _ => process::exit(1),
+        }
+    }
 }
 
 fn exec_process() {
@@ -170,10 +798,7 @@ fn exec_process() {
 }
 
 fn default_env(env: &str) -> String {
-    match env::var(env) {
-        Ok(env) => env,
-        Err(_) => String::from(""),
-    }
+    env::var(env).unwrap_or_default()
 }
 
 fn to_key_list<K, V>(map: &HashMap<K, V>) -> Vec<&K> {
@@ -213,11 +838,11 @@ fn display<T: ToString>(display_prompt: String, list: &[T], default: usize) -> u
         .unwrap()
 }
 
-fn select_profile(profile: &str) {
+pub fn select_profile(profile: &str) {
     env::set_var(AWS_DEFAULT_PROFILE, profile);
 }
 
-fn select_region(region: &str) {
+pub fn select_region(region: &str) {
     env::set_var(AWS_DEFAULT_REGION, region);
 }
 
@@ -0,0 +1,239 @@
+use std::env;
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::file::create_file_reader_for;
+use crate::file::home::get_awsp_file_path;
+
+const SESSIONS_FILE_NAME: &str = "sessions";
+const FIELD_SEPARATOR: char = '\t';
+const PROFILE_ENV_VAR_NAME: &str = "AWS_PROFILE";
+const REGION_ENV_VAR_NAMES: &[&str] = &["AWS_DEFAULT_REGION", "AWS_REGION"];
+const SESSION_TOKEN_ENV_VAR_NAME: &str = "AWS_SESSION_TOKEN";
+/// set by credential helpers such as aws-vault when they export temporary keys
+const EXPIRATION_ENV_VAR_NAME: &str = "AWS_CREDENTIAL_EXPIRATION";
+
+/// What awsp knew about a profile when switching to it, one line of `~/.awsp/sessions`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionState {
+    pub profile: String,
+    pub account: Option<String>,
+    /// expiry of the temporary credentials of the profile
+    pub expiry: Option<DateTime<Utc>>,
+}
+
+impl SessionState {
+    /// `profile<TAB>account<TAB>expiry`, unknown fields empty
+    fn to_line(&self) -> String {
+        [
+            self.profile.clone(),
+            self.account.clone().unwrap_or_default(),
+            self.expiry
+                .map(|expiry| expiry.to_rfc3339())
+                .unwrap_or_default(),
+        ]
+        .join(&FIELD_SEPARATOR.to_string())
+    }
+
+    fn from_line(line: &str) -> Option<SessionState> {
+        let mut fields = line.splitn(3, FIELD_SEPARATOR);
+
+        Some(SessionState {
+            profile: fields
+                .next()
+                .filter(|profile| !profile.is_empty())?
+                .to_string(),
+            account: fields
+                .next()
+                .filter(|account| !account.is_empty())
+                .map(String::from),
+            expiry: fields.next().and_then(|expiry| expiry.parse().ok()),
+        })
+    }
+}
+
+/// The profile active in this shell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub profile: String,
+    pub region: Option<String>,
+    pub account: Option<String>,
+    pub expiry: Option<DateTime<Utc>>,
+}
+
+impl Session {
+    /// Session of this shell from env vars and `~/.awsp/sessions` only, cheap enough for a prompt.
+    ///
+    /// # Return value
+    ///
+    /// `None` - If no profile is active
+    pub fn current() -> Option<Session> {
+        let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+
+        Session::resolve(
+            var(PROFILE_ENV_VAR_NAME),
+            REGION_ENV_VAR_NAMES.iter().find_map(|name| var(name)),
+            var(SESSION_TOKEN_ENV_VAR_NAME).is_some(),
+            var(EXPIRATION_ENV_VAR_NAME),
+            &load_session_states(),
+        )
+    }
+
+    /// Combine the env of the shell with the state recorded when switching.
+    /// An expiry exported in the env wins over the recorded one, which only counts
+    /// for the profile it was recorded for.
+    pub fn resolve(
+        profile: Option<String>,
+        region: Option<String>,
+        has_session_token: bool,
+        env_expiry: Option<String>,
+        states: &[SessionState],
+    ) -> Option<Session> {
+        let profile = profile?;
+        let state = states.iter().find(|state| state.profile == profile);
+        let env_expiry = env_expiry
+            .filter(|_| has_session_token)
+            .and_then(|expiry| expiry.parse::<DateTime<Utc>>().ok());
+
+        Some(Session {
+            region,
+            account: state.and_then(|state| state.account.clone()),
+            expiry: env_expiry.or_else(|| state.and_then(|state| state.expiry)),
+            profile,
+        })
+    }
+
+    /// Time left on temporary credentials, negative once expired.
+    pub fn expires_in(&self, now: DateTime<Utc>) -> Option<Duration> {
+        self.expiry.map(|expiry| expiry - now)
+    }
+}
+
+/// `42m`, `1h05m` or `expired` for the time left on temporary credentials.
+pub fn countdown_label(left: Duration) -> String {
+    if left.num_seconds() <= 0 {
+        "expired".to_string()
+    } else if left.num_hours() > 0 {
+        format!("{}h{:02}m", left.num_hours(), left.num_minutes() % 60)
+    } else {
+        format!("{}m", left.num_minutes())
+    }
+}
+
+/// Remember `state` for its profile in `~/.awsp/sessions`.
+pub fn record_session(state: &SessionState) -> io::Result<()> {
+    let location = get_awsp_file_path(SESSIONS_FILE_NAME)
+        .map_err(|e| io::Error::new(io::ErrorKind::NotFound, e.message))?;
+
+    write_session_to(location.as_path(), state)
+}
+
+pub fn load_session_states() -> Vec<SessionState> {
+    match get_awsp_file_path(SESSIONS_FILE_NAME) {
+        Ok(location) => read_session_states_from(location.as_path()),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn read_session_states_from(sessions_file_path: &Path) -> Vec<SessionState> {
+    if !sessions_file_path.is_file() {
+        return Vec::new();
+    }
+
+    create_file_reader_for(sessions_file_path)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| SessionState::from_line(&line))
+        .collect()
+}
+
+/// Replace the line of the profile of `state`, one line per profile.
+pub fn write_session_to(sessions_file_path: &Path, state: &SessionState) -> io::Result<()> {
+    let mut states = read_session_states_from(sessions_file_path);
+    states.retain(|recorded| recorded.profile != state.profile);
+    states.push(state.clone());
+
+    if let Some(parent) = sessions_file_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content = states
+        .iter()
+        .map(|state| state.to_line() + "\n")
+        .collect::<String>();
+    fs::write(sessions_file_path, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_dir::test_dir;
+
+    fn state(profile: &str, expiry: &str) -> SessionState {
+        SessionState {
+            profile: profile.to_string(),
+            account: Some("609629065308".to_string()),
+            expiry: expiry.parse().ok(),
+        }
+    }
+
+    #[test]
+    fn write_session_to_should_keep_one_line_per_profile() {
+        let dir_path = test_dir("sessions");
+        let sessions_file_path = dir_path.join("sessions");
+
+        write_session_to(&sessions_file_path, &state("prod", "2021-01-01T10:00:00Z")).unwrap();
+        write_session_to(&sessions_file_path, &state("dev", "")).unwrap();
+        write_session_to(&sessions_file_path, &state("prod", "2021-01-01T11:00:00Z")).unwrap();
+
+        assert_eq!(
+            read_session_states_from(&sessions_file_path),
+            vec![state("dev", ""), state("prod", "2021-01-01T11:00:00Z")]
+        );
+
+        fs::remove_dir_all(&dir_path).unwrap();
+    }
+
+    #[test]
+    fn resolve_should_use_recorded_state_of_active_profile_only() {
+        let states = vec![state("prod", "2021-01-01T10:00:00Z")];
+
+        let prod = Session::resolve(Some("prod".into()), None, false, None, &states).unwrap();
+        let dev = Session::resolve(Some("dev".into()), None, false, None, &states).unwrap();
+
+        assert_eq!(prod.account, Some("609629065308".to_string()));
+        assert_eq!(prod.expiry, "2021-01-01T10:00:00Z".parse().ok());
+        assert_eq!(dev.expiry, None);
+        assert_eq!(Session::resolve(None, None, false, None, &states), None);
+    }
+
+    #[test]
+    fn countdown_label_should_show_hours_and_minutes_left() {
+        assert_eq!(countdown_label(Duration::minutes(42)), "42m");
+        assert_eq!(countdown_label(Duration::minutes(65)), "1h05m");
+        assert_eq!(countdown_label(Duration::seconds(-1)), "expired");
+    }
+
+    #[test]
+    fn resolve_should_prefer_expiry_exported_with_session_token() {
+        let states = vec![state("prod", "2021-01-01T10:00:00Z")];
+        let env_expiry = Some("2021-01-01T12:00:00Z".to_string());
+
+        let with_token =
+            Session::resolve(Some("prod".into()), None, true, env_expiry.clone(), &states);
+        let without_token = Session::resolve(Some("prod".into()), None, false, env_expiry, &states);
+
+        assert_eq!(
+            with_token.unwrap().expiry,
+            "2021-01-01T12:00:00Z".parse().ok()
+        );
+        assert_eq!(
+            without_token.unwrap().expiry,
+            "2021-01-01T10:00:00Z".parse().ok()
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use dirs::home_dir;
+
+use crate::audit::Rotation;
+use crate::environment::EnvironmentRule;
+use crate::file::config::create_profile_config_map_from;
+use crate::file::helper::line::Sections;
+use crate::file::home::get_awsp_file_path;
+
+const SETTINGS_FILE_NAME: &str = "settings";
+const SETTINGS_SECTION: &str = "settings";
+const ENVIRONMENTS_SECTION: &str = "environments";
+const DEFAULT_HISTORY_LENGTH: usize = 100;
+const DEFAULT_AUDIT_MAX_SIZE: u64 = 1024 * 1024;
+const DEFAULT_AUDIT_KEEP: usize = 5;
+const DEFAULT_PROTECTED_ENVIRONMENTS: &[&str] = &["prod"];
+const DEFAULT_SYSTEM_CONFIG_FILE_PATH: &str = "/etc/awsp/config";
+
+/// How the region menu is laid out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RegionPicker {
+    /// geography first, then the regions of that geography
+    Grouped,
+    /// every region in one list
+    Flat,
+}
+
+/// Order of the profile menu.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProfileOrder {
+    Alphabetical,
+    /// order of appearance in the config file, then the credentials file
+    File,
+    /// most recently used first, the rest alphabetical
+    Recent,
+}
+
+/// awsp's own settings, read from the `[settings]` and `[environments]` sections of `~/.awsp/settings`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub region_picker: RegionPicker,
+    pub profile_order: ProfileOrder,
+    /// number of switches kept in `~/.awsp/history`
+    pub history_length: usize,
+    /// rotation of `~/.awsp/audit.log`
+    pub audit_rotation: Rotation,
+    /// environments whose profiles must be confirmed by typing their name
+    pub protected_environments: Vec<String>,
+    /// ask for a reason when switching to a protected environment
+    pub require_reason: bool,
+    /// profile name patterns per environment, sorted by environment
+    pub environment_rules: Vec<EnvironmentRule>,
+    /// directory of shared config files, `None` for `config.d` next to the config file
+    pub config_dir: Option<PathBuf>,
+    /// system-wide config file, read when it exists
+    pub system_config: PathBuf,
+    /// point `AWS_CONFIG_FILE` of started shells at a read-only merged config when shared
+    /// sections exist, instead of the user's own file
+    pub export_merged_config: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            region_picker: RegionPicker::Grouped,
+            profile_order: ProfileOrder::Alphabetical,
+            history_length: DEFAULT_HISTORY_LENGTH,
+            audit_rotation: Rotation {
+                max_size: DEFAULT_AUDIT_MAX_SIZE,
+                keep: DEFAULT_AUDIT_KEEP,
+            },
+            protected_environments: DEFAULT_PROTECTED_ENVIRONMENTS
+                .iter()
+                .map(|environment| environment.to_string())
+                .collect(),
+            require_reason: false,
+            environment_rules: Vec::new(),
+            config_dir: None,
+            system_config: PathBuf::from(DEFAULT_SYSTEM_CONFIG_FILE_PATH),
+            export_merged_config: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Settings from awsp's directory, defaults when the file does not exist.
+    pub fn load() -> Settings {
+        match get_awsp_file_path(SETTINGS_FILE_NAME) {
+            Ok(location) => Settings::from_file(location.as_path()),
+            Err(_) => Settings::default(),
+        }
+    }
+
+    pub fn from_file(settings_file_path: &Path) -> Settings {
+        let sections = match create_profile_config_map_from(settings_file_path, Sections::Titles) {
+            Some(sections) => sections,
+            None => return Settings::default(),
+        };
+
+        let mut result = sections
+            .get(SETTINGS_SECTION)
+            .map(Settings::from_map)
+            .unwrap_or_default();
+        if let Some(environments) = sections.get(ENVIRONMENTS_SECTION) {
+            result.environment_rules = environment_rules_from(environments);
+        }
+
+        result
+    }
+
+    fn from_map(settings: &HashMap<String, String>) -> Settings {
+        let mut result = Settings::default();
+
+        if let Some(region_picker) = settings.get("region_picker") {
+            result.region_picker = match region_picker.as_str() {
+                "flat" => RegionPicker::Flat,
+                _ => RegionPicker::Grouped,
+            };
+        }
+
+        if let Some(profile_order) = settings.get("profile_order") {
+            result.profile_order = match profile_order.as_str() {
+                "file" => ProfileOrder::File,
+                "recent" => ProfileOrder::Recent,
+                _ => ProfileOrder::Alphabetical,
+            };
+        }
+
+        if let Some(history_length) = settings.get("history_length") {
+            result.history_length = history_length.parse().unwrap_or(DEFAULT_HISTORY_LENGTH);
+        }
+
+        if let Some(audit_max_size) = settings.get("audit_max_size") {
+            result.audit_rotation.max_size =
+                audit_max_size.parse().unwrap_or(DEFAULT_AUDIT_MAX_SIZE);
+        }
+
+        if let Some(audit_keep) = settings.get("audit_keep") {
+            result.audit_rotation.keep = audit_keep.parse().unwrap_or(DEFAULT_AUDIT_KEEP);
+        }
+
+        if let Some(protected_environments) = settings.get("protected_environments") {
+            result.protected_environments = split_list(protected_environments);
+        }
+
+        if let Some(require_reason) = settings.get("require_reason") {
+            result.require_reason = require_reason.eq_ignore_ascii_case("true");
+        }
+
+        if let Some(config_dir) = settings.get("config_dir") {
+            result.config_dir = Some(expand_home(config_dir));
+        }
+
+        if let Some(system_config) = settings.get("system_config") {
+            result.system_config = expand_home(system_config);
+        }
+
+        if let Some(export_merged_config) = settings.get("export_merged_config") {
+            result.export_merged_config = export_merged_config.eq_ignore_ascii_case("true");
+        }
+
+        result
+    }
+}
+
+/// `prod = *-prod, prod-*` lines, one rule per pattern.
+fn environment_rules_from(environments: &HashMap<String, String>) -> Vec<EnvironmentRule> {
+    let mut environment_names = environments.keys().collect::<Vec<&String>>();
+    environment_names.sort();
+
+    environment_names
+        .into_iter()
+        .flat_map(|environment| {
+            split_list(&environments[environment])
+                .into_iter()
+                .map(move |pattern| EnvironmentRule {
+                    environment: environment.to_lowercase(),
+                    pattern,
+                })
+        })
+        .collect()
+}
+
+/// `path` with a leading `~/` resolved to the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), home_dir()) {
+        (Some(relative), Some(home)) => home.join(relative),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn from_file_should_read_settings_section() {
+        let result = Settings::from_file(Path::new("tests/sample-data/awsp_settings"));
+
+        assert_eq!(result.region_picker, RegionPicker::Flat);
+        assert_eq!(result.profile_order, ProfileOrder::Recent);
+        assert_eq!(result.history_length, 20);
+        assert_eq!(
+            result.audit_rotation,
+            Rotation {
+                max_size: 4096,
+                keep: 2
+            }
+        );
+        assert_eq!(result.protected_environments, vec!["prod", "staging"]);
+        assert!(result.require_reason);
+        assert_eq!(
+            result.config_dir,
+            Some(PathBuf::from("/opt/platform/aws-profiles"))
+        );
+        assert_eq!(result.system_config, PathBuf::from("/etc/awsp/config"));
+        assert!(result.export_merged_config);
+    }
+
+    #[test]
+    fn from_file_should_read_environment_rules_sorted_by_environment() {
+        let result = Settings::from_file(Path::new("tests/sample-data/awsp_settings"));
+
+        let rules = result
+            .environment_rules
+            .iter()
+            .map(|rule| (rule.environment.as_str(), rule.pattern.as_str()))
+            .collect::<Vec<(&str, &str)>>();
+
+        assert_eq!(
+            rules,
+            vec![("dev", "dev-*"), ("prod", "*-prod"), ("prod", "prod-*")]
+        );
+    }
+
+    #[test]
+    fn from_file_should_return_default_when_given_path_is_not_exist() {
+        let result = Settings::from_file(Path::new("some/nonsense/path"));
+
+        assert_eq!(result, Settings::default());
+    }
+}
@@ -0,0 +1,474 @@
+use std::collections::{HashMap, HashSet};
+use std::env::var;
+use std::path::Path;
+use std::str::FromStr;
+
+use rusoto_core::{HttpClient, Region};
+use rusoto_credential::{
+    AwsCredentials, CredentialsError, ProfileProvider, ProvideAwsCredentials, StaticProvider,
+};
+use rusoto_sts::{
+    GetCallerIdentityRequest, Sts, StsAssumeRoleSessionCredentialsProvider, StsClient,
+};
+use tokio::runtime::Builder;
+
+use crate::file::config::ProfileConfigMap;
+
+const STS_ENDPOINT_ENV_VAR_NAME: &str = "AWSP_STS_ENDPOINT";
+const REGION_ENV_VAR_NAMES: &[&str] = &["AWS_DEFAULT_REGION", "AWS_REGION"];
+const DEFAULT_STS_REGION: &str = "us-east-1";
+const ROLE_SESSION_NAME: &str = "awsp";
+/// keys of credential sources awsp cannot resolve itself, with the name to report them by
+const UNSUPPORTED_CREDENTIAL_SOURCES: &[(&str, &str)] = &[
+    ("sso_session", "SSO"),
+    ("sso_start_url", "SSO"),
+    ("credential_process", "credential_process"),
+    ("web_identity_token_file", "web identity"),
+];
+
+/// Role assumed on the way from a base profile to the requested one.
+struct RoleToAssume {
+    role_arn: String,
+    external_id: Option<String>,
+}
+
+/// Result of STS `GetCallerIdentity`.
+#[derive(Debug, PartialEq)]
+pub struct CallerIdentity {
+    pub account: String,
+    pub arn: String,
+    pub user_id: String,
+}
+
+impl CallerIdentity {
+    /// Check the resolved account against the account the profile is expected to land in.
+    /// Profiles without an expectation always match.
+    pub fn matches(&self, expected_account: Option<&str>) -> bool {
+        expected_account.is_none_or(|account| account == self.account)
+    }
+}
+
+/// Resolve `profile` credentials and call STS `GetCallerIdentity` with them.
+///
+/// # Argument
+///
+/// `profile` - profile name to resolve
+/// `config_map` - profile -> configs map of the aws config file
+/// `credentials_file_path` - path to aws shared credentials file
+/// `endpoint` - STS endpoint override, falls back to `AWSP_STS_ENDPOINT`
+pub fn get_caller_identity(
+    profile: &str,
+    config_map: &ProfileConfigMap,
+    credentials_file_path: &Path,
+    endpoint: Option<&str>,
+) -> Result<CallerIdentity, CredentialsError> {
+    let region = sts_region_for(profile, config_map, endpoint);
+    let runtime = Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| CredentialsError::new(format!("Failed to start runtime: {}", e)))?;
+
+    runtime.block_on(async {
+        let credentials =
+            resolve_credentials(profile, config_map, credentials_file_path, &region).await?;
+        let response = new_sts_client(credentials, &region)?
+            .get_caller_identity(GetCallerIdentityRequest {})
+            .await
+            .map_err(|e| CredentialsError::new(format!("GetCallerIdentity failed: {}", e)))?;
+
+        Ok(CallerIdentity {
+            account: response.account.unwrap_or_default(),
+            arn: response.arn.unwrap_or_default(),
+            user_id: response.user_id.unwrap_or_default(),
+        })
+    })
+}
+
+/// Account the profile should resolve to, taken from `sso_account_id` or the account of `role_arn`.
+pub fn expected_account_id_from(profile_config: &HashMap<String, String>) -> Option<String> {
+    profile_config.get("sso_account_id").cloned().or_else(|| {
+        profile_config
+            .get("role_arn")
+            .and_then(|arn| account_id_from_arn(arn))
+    })
+}
+
+/// Credential source awsp cannot resolve of the profile `profile` takes its credentials from,
+/// `None` for static keys and roles assumed from them.
+pub fn unsupported_credential_source_of(
+    profile: &str,
+    config_map: &ProfileConfigMap,
+) -> Option<&'static str> {
+    let (base_profile, _) = role_chain_for(profile, config_map).ok()?;
+    let base_config = config_map.get(base_profile)?;
+
+    UNSUPPORTED_CREDENTIAL_SOURCES
+        .iter()
+        .find(|(key, _)| base_config.contains_key(*key))
+        .map(|(_, source)| *source)
+}
+
+/// Profile holding the credentials of `profile` and the roles assumed from it, in assumption order.
+pub fn role_chain_of(
+    profile: &str,
+    config_map: &ProfileConfigMap,
+) -> Result<(String, Vec<String>), CredentialsError> {
+    let (base_profile, roles) = role_chain_for(profile, config_map)?;
+
+    Ok((
+        base_profile.to_string(),
+        roles.into_iter().rev().map(|role| role.role_arn).collect(),
+    ))
+}
+
+/// Extract account id from an ARN, `arn:partition:service:region:account-id:resource`.
+pub fn account_id_from_arn(arn: &str) -> Option<String> {
+    arn.split(':')
+        .nth(4)
+        .filter(|account| !account.is_empty())
+        .map(String::from)
+}
+
+async fn resolve_credentials(
+    profile: &str,
+    config_map: &ProfileConfigMap,
+    credentials_file_path: &Path,
+    region: &Region,
+) -> Result<AwsCredentials, CredentialsError> {
+    if let Some(source) = unsupported_credential_source_of(profile, config_map) {
+        return Err(CredentialsError::new(format!(
+            "{} credentials are not supported, profile: {}",
+            source, profile
+        )));
+    }
+    let (base_profile, roles) = role_chain_for(profile, config_map)?;
+
+    let mut credentials = ProfileProvider::with_configuration(credentials_file_path, base_profile)
+        .credentials()
+        .await?;
+
+    for role in roles.into_iter().rev() {
+        credentials = StsAssumeRoleSessionCredentialsProvider::new(
+            new_sts_client(credentials, region)?,
+            role.role_arn,
+            ROLE_SESSION_NAME.to_string(),
+            role.external_id,
+            None,
+            None,
+            None,
+        )
+        .credentials()
+        .await?;
+    }
+
+    Ok(credentials)
+}
+
+/// Follow `source_profile` links from `profile` until a profile without `role_arn`.
+/// Return that base profile with the roles to assume, outermost first.
+fn role_chain_for<'a>(
+    profile: &'a str,
+    config_map: &'a ProfileConfigMap,
+) -> Result<(&'a str, Vec<RoleToAssume>), CredentialsError> {
+    let mut roles = Vec::new();
+    let mut visited = HashSet::new();
+    let mut current = profile;
+
+    while let Some(role_arn) = config_map.get(current).and_then(|c| c.get("role_arn")) {
+        if !visited.insert(current) {
+            return Err(CredentialsError::new(format!(
+                "Circular source_profile reference at profile: {}",
+                current
+            )));
+        }
+
+        let profile_config = &config_map[current];
+        roles.push(RoleToAssume {
+            role_arn: role_arn.clone(),
+            external_id: profile_config.get("external_id").cloned(),
+        });
+        current = profile_config.get("source_profile").ok_or_else(|| {
+            CredentialsError::new(format!(
+                "Profile {} has role_arn but no source_profile.",
+                current
+            ))
+        })?;
+    }
+
+    Ok((current, roles))
+}
+
+fn new_sts_client(
+    credentials: AwsCredentials,
+    region: &Region,
+) -> Result<StsClient, CredentialsError> {
+    let http_client = HttpClient::new()
+        .map_err(|e| CredentialsError::new(format!("Failed to create http client: {}", e)))?;
+
+    Ok(StsClient::new_with(
+        http_client,
+        StaticProvider::from(credentials),
+        region.clone(),
+    ))
+}
+
+fn sts_region_for(profile: &str, config_map: &ProfileConfigMap, endpoint: Option<&str>) -> Region {
+    let name = config_map
+        .get(profile)
+        .and_then(|c| c.get("region").cloned())
+        .or_else(|| {
+            REGION_ENV_VAR_NAMES
+                .iter()
+                .find_map(|name| var(name).ok().filter(|v| !v.is_empty()))
+        })
+        .unwrap_or_else(|| DEFAULT_STS_REGION.to_string());
+
+    let endpoint = endpoint.map(String::from).or_else(|| {
+        var(STS_ENDPOINT_ENV_VAR_NAME)
+            .ok()
+            .filter(|v| !v.is_empty())
+    });
+
+    match endpoint {
+        Some(endpoint) => Region::Custom { name, endpoint },
+        None => Region::from_str(&name).unwrap_or_else(|_| Region::Custom {
+            endpoint: format!("https://sts.{}.amazonaws.com", name),
+            name,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::path::Path;
+    use std::thread;
+
+    use super::*;
+
+    const ACCOUNT: &str = "123456789012";
+
+    fn profile_config(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// Serve `body` to a single HTTP request on a random local port and return its endpoint.
+    fn serve_once(body: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            while !is_complete_request(&request) {
+                let read = stream.read(&mut buffer).unwrap();
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..read]);
+            }
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+
+        endpoint
+    }
+
+    fn is_complete_request(request: &[u8]) -> bool {
+        let request = String::from_utf8_lossy(request);
+        match request.find("\r\n\r\n") {
+            Some(header_end) => {
+                let content_length = request[..header_end]
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        if name.eq_ignore_ascii_case("content-length") {
+                            value.trim().parse::<usize>().ok()
+                        } else {
+                            None
+                        }
+                    })
+                    .unwrap_or(0);
+                request.len() >= header_end + 4 + content_length
+            }
+            None => false,
+        }
+    }
+
+    #[test]
+    fn get_caller_identity_should_return_identity_from_given_endpoint() {
+        let body =
+            fs::read_to_string("tests/sample-data/sts_get_caller_identity_response").unwrap();
+        let endpoint = serve_once(body);
+
+        let result = get_caller_identity(
+            "default",
+            &ProfileConfigMap::new(),
+            Path::new("tests/sample-data/default_profile_credentials"),
+            Some(&endpoint),
+        )
+        .unwrap();
+
+        assert_eq!(result.account, ACCOUNT);
+        assert_eq!(result.arn, "arn:aws:iam::123456789012:user/awsp");
+        assert_eq!(result.user_id, "AIDAEXAMPLEUSERID");
+    }
+
+    #[test]
+    fn get_caller_identity_should_return_error_when_profile_has_no_credentials() {
+        let result = get_caller_identity(
+            "nonexistent",
+            &ProfileConfigMap::new(),
+            Path::new("tests/sample-data/default_profile_credentials"),
+            Some("http://127.0.0.1:1"),
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn get_caller_identity_should_refuse_credential_sources_it_cannot_resolve() {
+        let mut config_map = ProfileConfigMap::new();
+        config_map.insert(
+            "sso".to_string(),
+            profile_config(&[("sso_session", "corp"), ("sso_account_id", ACCOUNT)]),
+        );
+        config_map.insert(
+            "admin".to_string(),
+            profile_config(&[
+                ("role_arn", "arn:aws:iam::123456789012:role/admin"),
+                ("source_profile", "sso"),
+            ]),
+        );
+
+        assert_eq!(
+            unsupported_credential_source_of("admin", &config_map),
+            Some("SSO")
+        );
+        assert_eq!(
+            unsupported_credential_source_of("default", &config_map),
+            None
+        );
+        let result = get_caller_identity(
+            "admin",
+            &config_map,
+            Path::new("tests/sample-data/default_profile_credentials"),
+            Some("http://127.0.0.1:1"),
+        );
+        assert!(result.unwrap_err().message.contains("not supported"));
+    }
+
+    #[test]
+    fn expected_account_id_from_should_prefer_sso_account_id() {
+        let config = profile_config(&[
+            ("sso_account_id", "111111111111"),
+            ("role_arn", "arn:aws:iam::222222222222:role/admin"),
+        ]);
+
+        assert_eq!(
+            expected_account_id_from(&config),
+            Some("111111111111".into())
+        );
+    }
+
+    #[test]
+    fn expected_account_id_from_should_use_role_arn_account() {
+        let config = profile_config(&[("role_arn", "arn:aws:iam::222222222222:role/admin")]);
+
+        assert_eq!(
+            expected_account_id_from(&config),
+            Some("222222222222".into())
+        );
+    }
+
+    #[test]
+    fn caller_identity_matches_should_flag_different_account() {
+        let identity = CallerIdentity {
+            account: ACCOUNT.into(),
+            arn: String::new(),
+            user_id: String::new(),
+        };
+
+        assert!(identity.matches(None));
+        assert!(identity.matches(Some(ACCOUNT)));
+        assert!(!identity.matches(Some("999999999999")));
+    }
+
+    #[test]
+    fn role_chain_for_should_follow_source_profiles() {
+        let mut config_map = ProfileConfigMap::new();
+        config_map.insert(
+            "admin".into(),
+            profile_config(&[
+                ("role_arn", "arn:aws:iam::222222222222:role/admin"),
+                ("source_profile", "base"),
+            ]),
+        );
+        config_map.insert("base".into(), profile_config(&[("region", "us-east-1")]));
+
+        let (base, roles) = role_chain_for("admin", &config_map).unwrap();
+
+        assert_eq!(base, "base");
+        assert_eq!(roles.len(), 1);
+        assert_eq!(roles[0].role_arn, "arn:aws:iam::222222222222:role/admin");
+    }
+
+    #[test]
+    fn role_chain_of_should_list_roles_in_assumption_order() {
+        let mut config_map = ProfileConfigMap::new();
+        config_map.insert(
+            "admin".into(),
+            profile_config(&[
+                ("role_arn", "arn:aws:iam::2:role/admin"),
+                ("source_profile", "jump"),
+            ]),
+        );
+        config_map.insert(
+            "jump".into(),
+            profile_config(&[
+                ("role_arn", "arn:aws:iam::1:role/jump"),
+                ("source_profile", "base"),
+            ]),
+        );
+
+        let (base, roles) = role_chain_of("admin", &config_map).unwrap();
+
+        assert_eq!(base, "base");
+        assert_eq!(
+            roles,
+            vec!["arn:aws:iam::1:role/jump", "arn:aws:iam::2:role/admin"]
+        );
+    }
+
+    #[test]
+    fn role_chain_for_should_return_error_when_source_profiles_are_circular() {
+        let mut config_map = ProfileConfigMap::new();
+        config_map.insert(
+            "a".into(),
+            profile_config(&[
+                ("role_arn", "arn:aws:iam::1:role/a"),
+                ("source_profile", "b"),
+            ]),
+        );
+        config_map.insert(
+            "b".into(),
+            profile_config(&[
+                ("role_arn", "arn:aws:iam::1:role/b"),
+                ("source_profile", "a"),
+            ]),
+        );
+
+        assert!(role_chain_for("a", &config_map).is_err());
+    }
+}
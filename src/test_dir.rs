@@ -0,0 +1,20 @@
+use std::env::temp_dir;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static TEST_DIR_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// A new empty directory for one test, named after `name`, the process and a counter so that
+/// tests running at the same time, in this process or another, never share it.
+pub fn test_dir(name: &str) -> PathBuf {
+    let dir_path = temp_dir().join(format!(
+        "awsp-{}-test-{}-{}",
+        name,
+        process::id(),
+        TEST_DIR_COUNT.fetch_add(1, Ordering::SeqCst)
+    ));
+    fs::create_dir_all(&dir_path).unwrap();
+    dir_path
+}